@@ -61,7 +61,7 @@ impl IsTable for Product{
                     name:"product_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:true, is_unique:false, not_null:true, is_inherited:false, 
+                    is_primary:true, is_unique:false, not_null:true, is_inherited:false, collation:None, is_identity:false, is_generated:false, 
                     default:Some("uuid_generate_v4()".to_string()),
                     comment:None,
                     foreign:None,
@@ -70,7 +70,7 @@ impl IsTable for Product{
                     name:"name".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"character varying".to_string(),
-                    is_primary:false, is_unique:false, not_null:true, is_inherited:false, 
+                    is_primary:false, is_unique:false, not_null:true, is_inherited:false, collation:None, is_identity:false, is_generated:false, 
                     default:None,
                     comment:None,
                     foreign:None,
@@ -79,13 +79,14 @@ impl IsTable for Product{
                     name:"description".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"character varying".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, 
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false, 
                     default:None,
                     comment:None,
                     foreign:None,
                 },
             ],
             is_view: false,
+            foreign_keys: vec![],
         }
     }
 }
@@ -49,6 +49,7 @@ impl IsTable for Photo{
             comment: None,
             columns: vec![],
             is_view: false,
+            foreign_keys: vec![],
         }
     }
 }
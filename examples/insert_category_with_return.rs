@@ -100,6 +100,7 @@ impl IsTable for Category{
             comment: None,
             columns: vec![],
             is_view: false,
+            foreign_keys: vec![],
         }
     }
 }
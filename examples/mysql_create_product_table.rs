@@ -269,7 +269,7 @@ impl IsTable for Product{
                     name:"organization_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("@Value(users.user_id) , which means the value will be set with the users.user_id value\n\n@Where(users.active=true)".to_string()),
                     foreign:None,
@@ -278,7 +278,7 @@ impl IsTable for Product{
                     name:"client_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("@Value(users.client_id) The client_id of the user creating this records".to_string()),
                     foreign:None,
@@ -287,7 +287,7 @@ impl IsTable for Product{
                     name:"created".to_string(),
                     data_type:"DateTime<UTC>".to_string(),
                     db_data_type:"timestamp with time zone".to_string(),
-                    is_primary:false, is_unique:false, not_null:true, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:true, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:Some("now()".to_string()),
                     comment:None,
                     foreign:None,
@@ -296,7 +296,7 @@ impl IsTable for Product{
                     name:"created_by".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("@Value(users.user_id)".to_string()),
                     foreign:None,
@@ -305,7 +305,7 @@ impl IsTable for Product{
                     name:"updated".to_string(),
                     data_type:"DateTime<UTC>".to_string(),
                     db_data_type:"timestamp with time zone".to_string(),
-                    is_primary:false, is_unique:false, not_null:true, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:true, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:Some("now()".to_string()),
                     comment:None,
                     foreign:None,
@@ -314,7 +314,7 @@ impl IsTable for Product{
                     name:"updated_by".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("@Value(users.user_id)".to_string()),
                     foreign:None,
@@ -323,7 +323,7 @@ impl IsTable for Product{
                     name:"priority".to_string(),
                     data_type:"f64".to_string(),
                     db_data_type:"numeric".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:None,
@@ -332,7 +332,7 @@ impl IsTable for Product{
                     name:"name".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"character varying".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("This is @Required it has @DisplayLength(50) - 50 character in display length a @MinLength(1) and @MaxLength(100) - Do not go over 100 characters or else the system will throw a ValueTooLong exception\ncan also be express with @Length(1-100)".to_string()),
                     foreign:None,
@@ -341,7 +341,7 @@ impl IsTable for Product{
                     name:"description".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"character varying".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("@DisplayLength(100) When building a UI for this field\n@MaxLength(200) Do not go over 200 character on this one".to_string()),
                     foreign:None,
@@ -350,7 +350,7 @@ impl IsTable for Product{
                     name:"help".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"text".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:None,
@@ -359,7 +359,7 @@ impl IsTable for Product{
                     name:"active".to_string(),
                     data_type:"bool".to_string(),
                     db_data_type:"boolean".to_string(),
-                    is_primary:false, is_unique:false, not_null:true, is_inherited:true,
+                    is_primary:false, is_unique:false, not_null:true, is_inherited:true, collation:None, is_identity:false, is_generated:false,
                     default:Some("true".to_string()),
                     comment:Some("@Active".to_string()),
                     foreign:None,
@@ -368,7 +368,7 @@ impl IsTable for Product{
                     name:"product_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:true, is_unique:false, not_null:true, is_inherited:false,
+                    is_primary:true, is_unique:false, not_null:true, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:Some("uuid_generate_v4()".to_string()),
                     comment:None,
                     foreign:None,
@@ -377,7 +377,7 @@ impl IsTable for Product{
                     name:"parent_product_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:None,
@@ -386,7 +386,7 @@ impl IsTable for Product{
                     name:"is_service".to_string(),
                     data_type:"bool".to_string(),
                     db_data_type:"boolean".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:Some("false".to_string()),
                     comment:None,
                     foreign:None,
@@ -395,7 +395,7 @@ impl IsTable for Product{
                     name:"price".to_string(),
                     data_type:"f64".to_string(),
                     db_data_type:"numeric".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:None,
@@ -404,7 +404,7 @@ impl IsTable for Product{
                     name:"use_parent_price".to_string(),
                     data_type:"bool".to_string(),
                     db_data_type:"boolean".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:Some("false".to_string()),
                     comment:None,
                     foreign:None,
@@ -413,7 +413,7 @@ impl IsTable for Product{
                     name:"unit".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"character varying".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:None,
@@ -422,7 +422,7 @@ impl IsTable for Product{
                     name:"tags".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"json".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:None,
@@ -431,7 +431,7 @@ impl IsTable for Product{
                     name:"info".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"json".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("{color:\"red\",\ndimension:\"10x20x30\",\ndimensionUnit:\"mm\",\nweight:\"4\",\nweightUnit:\"kg\"\n}".to_string()),
                     foreign:None,
@@ -440,7 +440,7 @@ impl IsTable for Product{
                     name:"seq_no".to_string(),
                     data_type:"i32".to_string(),
                     db_data_type:"integer".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("@Sequence can be used to do alternate ordering of the values, when alphetical or time can not be used".to_string()),
                     foreign:None,
@@ -449,7 +449,7 @@ impl IsTable for Product{
                     name:"upfront_fee".to_string(),
                     data_type:"f64".to_string(),
                     db_data_type:"numeric".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:Some("0.00".to_string()),
                     comment:Some("Applicable to services, usually services has an upfront fee".to_string()),
                     foreign:None,
@@ -458,7 +458,7 @@ impl IsTable for Product{
                     name:"barcode".to_string(),
                     data_type:"String".to_string(),
                     db_data_type:"character varying".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("barcode if scanning the product, conflict can happen, expect to return matching list of products using the barcode".to_string()),
                     foreign:None,
@@ -467,7 +467,7 @@ impl IsTable for Product{
                     name:"owner_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:Some("Whom this product belongs, since created_by can be someone else create the product list in behalf of the owner of the product".to_string()),
                     foreign:Some(
@@ -481,7 +481,7 @@ impl IsTable for Product{
                     name:"currency_id".to_string(),
                     data_type:"Uuid".to_string(),
                     db_data_type:"uuid".to_string(),
-                    is_primary:false, is_unique:false, not_null:false, is_inherited:false,
+                    is_primary:false, is_unique:false, not_null:false, is_inherited:false, collation:None, is_identity:false, is_generated:false,
                     default:None,
                     comment:None,
                     foreign:Some(
@@ -492,7 +492,8 @@ impl IsTable for Product{
                         }),
                 },
             ],
-            is_view: false
+            is_view: false,
+            foreign_keys: vec![],
         }
     }
 }
@@ -62,10 +62,10 @@ fn main() {
          .filter("product.name", Equality::EQ, &"GTX660 Ti videocard")
          .filter("category.name", Equality::EQ, &"Electronic")
          .group_by(vec!["category.name"])
-         .having("count(*)", Equality::GT, &1)
+         .having_raw("count(*)", Equality::GT, &1)
          .asc("product.name")
          .desc("product.created");
-    let frag = query.build(db.as_ref());
+    let frag = query.build(db.as_ref()).unwrap();
 
     let expected = "
 SELECT product.product_id AS product_product_id, product.name AS product_name, category.product_id AS category_product_id, 
@@ -57,6 +57,7 @@ impl IsTable for Product{
             comment: None,
             columns: vec![],
             is_view: false,
+            foreign_keys: vec![],
         }
     }
 }
@@ -0,0 +1,1599 @@
+use query::Query;
+use dao::Value;
+use writer::SqlFrag;
+use query::{Connector, Equality, Operand, Field};
+use query::{Direction, Modifier, JoinType};
+use query::{Filter, Condition};
+use query::{Window, FrameUnit, FrameBound};
+use query::Function;
+use query::SqlType;
+use query::GroupingSet;
+use query::LockMode;
+use query::OnConflict;
+use query::{TableSample, SampleMethod};
+use database::SqlOption;
+use database::DbError;
+use table::{Table, Column, ForeignKey, Index};
+use std::time::Duration;
+
+
+/// generates the sql of a query for a dialect,
+/// only the sql options of the database are needed, no connection is involved
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SqlBuilder {
+    pub sql_options: Vec<SqlOption>,
+}
+
+impl SqlBuilder{
+
+    pub fn new(sql_options: Vec<SqlOption>) -> Self {
+        SqlBuilder { sql_options: sql_options }
+    }
+
+    fn sql_options(&self) -> Vec<SqlOption> {
+        self.sql_options.clone()
+    }
+
+    /// build a query, return the sql string and the parameters.
+    /// use by select to build the select query
+    /// build all types of query,
+    /// the query fails to build when it uses a feature this database doesn't support
+    pub fn build_query(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        match query.sql_type {
+            SqlType::SELECT => self.build_select(query),
+            SqlType::INSERT => self.build_insert(query),
+            SqlType::UPDATE => self.build_update(query),
+            SqlType::DELETE => self.build_delete(query),
+        }
+    }
+
+    /// build operand, i.e: columns, query, function, values
+    fn build_operand(&self,
+                     w: &mut SqlFrag,
+                     parent_query: &Query,
+                     operand: &Operand)
+                     -> Result<(), DbError> {
+        match *operand {
+            Operand::ColumnName(ref column_name) => {
+                if parent_query.joins.is_empty() && !parent_query.is_subquery {
+                    w.column_name(&column_name.column);
+                } else {
+                    w.column_name(&column_name.complete_name());
+                }
+            }
+            Operand::TableName(ref table_name) => {
+                if self.sql_options().contains(&SqlOption::UsesSchema) {
+                    w.append(&table_name.complete_name());
+                } else {
+                    w.append(&table_name.name);
+                }
+            }
+            Operand::Function(ref function) => {
+                if function.function == "STRING_AGG" {
+                    try!(self.build_string_agg(w, parent_query, function));
+                } else {
+                    match function.schema {
+                        Some(ref schema) if self.sql_options().contains(&SqlOption::UsesSchema) => {
+                            w.append(schema);
+                            w.append(".");
+                        }
+                        _ => (),
+                    }
+                    w.append(&function.function);
+                    w.append("(");
+                    if function.distinct {
+                        w.keyword("DISTINCT ");
+                    }
+                    match function.filter {
+                        Some(ref filter) if !self.sql_options()
+                                                 .contains(&SqlOption::SupportsAggregateFilter) => {
+                            try!(self.build_case_filtered_params(w,
+                                                                 parent_query,
+                                                                 function,
+                                                                 filter));
+                        }
+                        _ => try!(self.build_operands(w, parent_query, &function.params)),
+                    }
+                    w.append(")");
+                    try!(self.build_aggregate_filter(w, parent_query, function));
+                }
+                if function.requires_over() && function.over.is_none() {
+                    return Err(DbError::from_string(format!("{} requires an OVER clause",
+                                                            function.function)));
+                }
+                match function.over {
+                    Some(ref window) => try!(self.build_window(w, parent_query, window)),
+                    None => (),
+                }
+            }
+            Operand::Query(ref q) => {
+                // columns of a subquery are rendered with their table names
+                // to allow referencing the columns of the outer query
+                let mut subquery = q.clone();
+                subquery.is_subquery = true;
+                subquery.finalize();
+                w.append("(");
+                try!(self.write_select(w, &subquery));
+                w.append(")");
+            }
+            Operand::Value(ref value) => {
+                w.parameter(value.clone());
+            }
+            Operand::Raw(ref sql) => {
+                w.append(sql);
+            }
+            Operand::Default => {
+                w.keyword("DEFAULT");
+            }
+            Operand::NowMinus(ref duration) => {
+                let seconds = duration.as_secs();
+                if self.sql_options().contains(&SqlOption::UsesDatetimeModifier) {
+                    w.append(&format!("DATETIME('now', '-{} seconds')", seconds));
+                } else if self.sql_options().contains(&SqlOption::UsesIntervalUnit) {
+                    w.keyword("NOW() - INTERVAL ");
+                    w.append(&format!("{}", seconds));
+                    w.keyword(" SECOND");
+                } else {
+                    w.keyword("NOW() - INTERVAL ");
+                    w.append(&format!("'{} seconds'", seconds));
+                }
+            }
+            Operand::Any(ref q) => {
+                if !self.sql_options().contains(&SqlOption::SupportsAnyAll) {
+                    return Err(DbError::new("ANY with a subquery is not supported in this \
+                                             database"));
+                }
+                w.keyword("ANY ");
+                try!(self.build_operand(w, parent_query, &Operand::Query(q.clone())));
+            }
+            Operand::All(ref q) => {
+                if !self.sql_options().contains(&SqlOption::SupportsAnyAll) {
+                    return Err(DbError::new("ALL with a subquery is not supported in this \
+                                             database"));
+                }
+                w.keyword("ALL ");
+                try!(self.build_operand(w, parent_query, &Operand::Query(q.clone())));
+            }
+            Operand::AnyArray(ref value) => {
+                if !self.sql_options().contains(&SqlOption::SupportsArrayParam) {
+                    return Err(DbError::new("ANY with an array parameter is not supported in this \
+                                             database"));
+                }
+                w.keyword("ANY(");
+                w.parameter(value.clone());
+                w.append(")");
+            }
+            Operand::Row(ref operands) => {
+                w.append("(");
+                try!(self.build_operands(w, parent_query, operands));
+                w.append(")");
+            }
+            Operand::Case(ref case) => {
+                w.keyword("CASE");
+                for &(ref condition, ref then) in &case.whens {
+                    w.sp_once();
+                    w.keyword("WHEN ");
+                    try!(self.build_condition(w, parent_query, condition));
+                    w.sp_once();
+                    w.keyword("THEN ");
+                    try!(self.build_operand(w, parent_query, then));
+                }
+                match case.otherwise {
+                    Some(ref otherwise) => {
+                        w.sp_once();
+                        w.keyword("ELSE ");
+                        try!(self.build_operand(w, parent_query, otherwise));
+                    }
+                    None => (),
+                }
+                w.sp_once();
+                w.keyword("END");
+            }
+            Operand::Vec(ref operands) => {
+                let mut do_comma = false;
+                if !operands.is_empty() {
+                    w.append("(");
+                    for op in operands {
+                        if do_comma {
+                            w.commasp();
+                        } else {
+                            do_comma = true;
+                        }
+                        try!(self.build_operand(w, parent_query, op));
+                    }
+                    w.append(")");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// build a comma separated list of operands
+    fn build_operands(&self,
+                      w: &mut SqlFrag,
+                      parent_query: &Query,
+                      operands: &Vec<Operand>)
+                      -> Result<(), DbError> {
+        let mut do_comma = false;
+        for operand in operands {
+            if do_comma {
+                w.commasp();
+            } else {
+                do_comma = true;
+            }
+            try!(self.build_operand(w, parent_query, operand));
+        }
+        Ok(())
+    }
+
+    /// build the string aggregate in the dialect of the database
+    /// ie: `STRING_AGG(name, $1)`, `GROUP_CONCAT(name SEPARATOR ', ')`, `GROUP_CONCAT(name, $1)`
+    fn build_string_agg(&self,
+                        w: &mut SqlFrag,
+                        parent_query: &Query,
+                        function: &Function)
+                        -> Result<(), DbError> {
+        if function.params.len() != 2 {
+            return Err(DbError::new("STRING_AGG expects a column and a separator"));
+        }
+        let column = &function.params[0];
+        let separator = &function.params[1];
+        if self.sql_options().contains(&SqlOption::UsesGroupConcatSeparator) {
+            w.append("GROUP_CONCAT(");
+            try!(self.build_aggregated_column(w, parent_query, function, column));
+            w.keyword(" SEPARATOR ");
+            // the separator has to be a string literal, it can not be a parameter
+            match *separator {
+                Operand::Value(Value::String(ref sep)) => w.string_literal(sep),
+                _ => return Err(DbError::new("The separator of GROUP_CONCAT must be a string")),
+            };
+            w.append(")");
+        } else {
+            if self.sql_options().contains(&SqlOption::UsesGroupConcat) {
+                w.append("GROUP_CONCAT(");
+            } else {
+                w.append("STRING_AGG(");
+            }
+            try!(self.build_aggregated_column(w, parent_query, function, column));
+            w.commasp();
+            try!(self.build_operand(w, parent_query, separator));
+            w.append(")");
+        }
+        try!(self.build_aggregate_filter(w, parent_query, function));
+        Ok(())
+    }
+
+    /// build the OVER clause of a window function
+    fn build_window(&self,
+                    w: &mut SqlFrag,
+                    parent_query: &Query,
+                    window: &Window)
+                    -> Result<(), DbError> {
+        w.keyword(" OVER (");
+        let mut do_space = false;
+        if !window.partition_by.is_empty() {
+            w.keyword("PARTITION BY ");
+            try!(self.build_operands(w, parent_query, &window.partition_by));
+            do_space = true;
+        }
+        if !window.order_by.is_empty() {
+            if do_space {
+                w.sp();
+            }
+            w.keyword("ORDER BY ");
+            let mut do_comma = false;
+            for &(ref column, ref direction) in &window.order_by {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.append(&column);
+                match *direction {
+                    Direction::ASC => w.keyword(" ASC"),
+                    Direction::DESC => w.keyword(" DESC"),
+                };
+            }
+            do_space = true;
+        }
+        match window.frame {
+            Some(ref frame) => {
+                if do_space {
+                    w.sp();
+                }
+                match frame.unit {
+                    FrameUnit::ROWS => w.keyword("ROWS BETWEEN "),
+                    FrameUnit::RANGE => w.keyword("RANGE BETWEEN "),
+                };
+                self.build_frame_bound(w, &frame.start);
+                w.keyword(" AND ");
+                self.build_frame_bound(w, &frame.end);
+            }
+            None => (),
+        }
+        w.append(")");
+        Ok(())
+    }
+
+    /// the aggregated param is NULL on the rows not matching the filter,
+    /// which are skipped by the aggregate, ie: `COUNT(CASE WHEN active THEN 1 END)`
+    fn build_case_filtered_params(&self,
+                                  w: &mut SqlFrag,
+                                  parent_query: &Query,
+                                  function: &Function,
+                                  filter: &Filter) -> Result<(), DbError> {
+        // COUNT(*) counts the rows, a constant is counted in place of *
+        let is_star = match function.params.first() {
+            Some(&Operand::Raw(ref sql)) => sql.trim() == "*",
+            Some(&Operand::ColumnName(ref column)) => column.column == "*",
+            Some(_) => false,
+            None => true,
+        };
+        if is_star {
+            try!(self.build_case_filtered(w, parent_query, filter, &Operand::Raw("1".to_string())));
+        } else {
+            try!(self.build_case_filtered(w, parent_query, filter, &function.params[0]));
+        }
+        // the rest of the params, ie: the separator, are not filtered
+        for param in function.params.iter().skip(1) {
+            w.commasp();
+            try!(self.build_operand(w, parent_query, param));
+        }
+        Ok(())
+    }
+
+    /// `CASE WHEN filter THEN operand END`, NULL on the rows not matching the filter
+    fn build_case_filtered(&self,
+                           w: &mut SqlFrag,
+                           parent_query: &Query,
+                           filter: &Filter,
+                           operand: &Operand) -> Result<(), DbError> {
+        w.keyword("CASE WHEN ");
+        try!(self.build_filter(w, parent_query, filter));
+        w.sp_once();
+        w.keyword("THEN ");
+        try!(self.build_operand(w, parent_query, operand));
+        w.sp_once();
+        w.keyword("END");
+        Ok(())
+    }
+
+    /// the aggregated column of STRING_AGG, filtered with CASE WHEN
+    /// when FILTER (WHERE ..) is not supported
+    fn build_aggregated_column(&self,
+                               w: &mut SqlFrag,
+                               parent_query: &Query,
+                               function: &Function,
+                               column: &Operand) -> Result<(), DbError> {
+        match function.filter {
+            Some(ref filter) if !self.sql_options()
+                                     .contains(&SqlOption::SupportsAggregateFilter) => {
+                try!(self.build_case_filtered(w, parent_query, filter, column));
+            }
+            _ => try!(self.build_operand(w, parent_query, column)),
+        }
+        Ok(())
+    }
+
+    /// the FILTER (WHERE ..) clause of an aggregate, when supported
+    fn build_aggregate_filter(&self,
+                              w: &mut SqlFrag,
+                              parent_query: &Query,
+                              function: &Function)
+                              -> Result<(), DbError> {
+        match function.filter {
+            Some(ref filter) if self.sql_options()
+                                    .contains(&SqlOption::SupportsAggregateFilter) => {
+                w.keyword(" FILTER (WHERE ");
+                try!(self.build_filter(w, parent_query, filter));
+                w.append(")");
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn build_frame_bound(&self, w: &mut SqlFrag, bound: &FrameBound) {
+        match *bound {
+            FrameBound::UNBOUNDED_PRECEDING => w.keyword("UNBOUNDED PRECEDING"),
+            FrameBound::PRECEDING(n) => w.append(&format!("{}", n)).keyword(" PRECEDING"),
+            FrameBound::CURRENT_ROW => w.keyword("CURRENT ROW"),
+            FrameBound::FOLLOWING(n) => w.append(&format!("{}", n)).keyword(" FOLLOWING"),
+            FrameBound::UNBOUNDED_FOLLOWING => w.keyword("UNBOUNDED FOLLOWING"),
+        };
+    }
+
+    fn build_condition(&self,
+                       w: &mut SqlFrag,
+                       parent_query: &Query,
+                       cond: &Condition)
+                       -> Result<(), DbError> {
+        match cond.equality {
+            Equality::RAW => {
+                try!(self.build_raw_condition(w, cond));
+                return Ok(());
+            }
+            Equality::IS_DISTINCT_FROM if self.sql_options()
+                                              .contains(&SqlOption::UsesNullSafeEqual) => {
+                w.keyword("NOT (");
+                try!(self.build_operand(w, parent_query, &cond.left));
+                w.append(" <=> ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+                w.append(")");
+                return Ok(());
+            }
+            _ => (),
+        }
+        try!(self.build_operand(w, parent_query, &cond.left));
+        w.append(" ");
+        match cond.equality {
+            Equality::EQ => {
+                w.append("= ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::NEQ => {
+                w.append("!= ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::LT => {
+                w.append("< ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::LTE => {
+                w.append("<= ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::GT => {
+                w.append("> ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::GTE => {
+                w.append(">= ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::IN => {
+                w.keyword("IN ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::NOT_IN => {
+                w.keyword("NOT IN ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::LIKE => {
+                w.keyword("LIKE ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::IS_NOT_NULL => {
+                w.keyword("IS NOT NULL");
+            }
+
+            Equality::IS_NULL => {
+                w.keyword("IS NULL");
+            }
+            Equality::IS_DISTINCT_FROM => {
+                w.keyword("IS DISTINCT FROM ");
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::IS_NOT_DISTINCT_FROM => {
+                if self.sql_options().contains(&SqlOption::UsesNullSafeEqual) {
+                    w.append("<=> ");
+                } else {
+                    w.keyword("IS NOT DISTINCT FROM ");
+                }
+                try!(self.build_operand(w, parent_query, &cond.right));
+            }
+            Equality::RAW => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// the raw sql is on the left, the values of its placeholders is on the right
+    fn build_raw_condition(&self, w: &mut SqlFrag, cond: &Condition) -> Result<(), DbError> {
+        let mut values = vec![];
+        match cond.right {
+            Operand::Vec(ref operands) => {
+                for op in operands {
+                    match *op {
+                        Operand::Value(ref value) => values.push(value.clone()),
+                        _ => return Err(DbError::new("Only values are bound to a raw condition")),
+                    }
+                }
+            }
+            _ => return Err(DbError::new("Values of a raw condition should be in a Vec")),
+        }
+        match cond.left {
+            Operand::Raw(ref sql) => {
+                w.append_raw(sql, &values);
+            }
+            _ => return Err(DbError::new("Expecting a raw sql on a raw condition")),
+        }
+        Ok(())
+    }
+
+    fn build_field(&self,
+                   w: &mut SqlFrag,
+                   parent_query: &Query,
+                   field: &Field)
+                   -> Result<(), DbError> {
+        try!(self.build_operand(w, parent_query, &field.operand));
+        match field.name {
+            Some(ref name) => {
+                w.keyword(" AS ");
+                w.append(name);
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+
+    fn build_filter(&self,
+                    w: &mut SqlFrag,
+                    parent_query: &Query,
+                    filter: &Filter)
+                    -> Result<(), DbError> {
+        if !filter.subfilters.is_empty() {
+            w.append("( ");
+        }
+        try!(self.build_condition(w, parent_query, &filter.condition));
+        for filt in &filter.subfilters {
+            match filt.connector {
+                Connector::And => {
+                    w.keyword("AND ");
+                }
+                Connector::Or => {
+                    w.keyword("OR ");
+                }
+            }
+            try!(self.build_filter(w, parent_query, filt));// build sub filters as well
+        }
+        if !filter.subfilters.is_empty() {
+            w.append(" )");
+        }
+        Ok(())
+    }
+
+    /// build the filter clause or the where clause of the query
+    /// TODO: add the sub filters
+    fn build_filters(&self,
+                     w: &mut SqlFrag,
+                     parent_query: &Query,
+                     filters: &Vec<Filter>)
+                     -> Result<(), DbError> {
+        let mut do_and = false;
+        for filter in filters {
+            if do_and {
+                w.left_river("AND ");
+            } else {
+                do_and = true;
+            }
+            try!(self.build_filter(w, parent_query, filter));
+        }
+        Ok(())
+    }
+
+    /// build the enumerated, distinct, *, columns
+    fn build_enumerated_fields(&self,
+                               w: &mut SqlFrag,
+                               parent_query: &Query,
+                               enumerated_fields: &Vec<Field>) -> Result<(), DbError> {
+        let mut do_comma = false;
+        let mut cnt = 0;
+        for field in enumerated_fields {
+            if do_comma {
+                w.commasp();
+            } else {
+                do_comma = true;
+            }
+            cnt += 1;
+            if cnt % 4 == 0 {//break at every 4 columns to encourage sql tuning/revising
+                w.left_river("");
+            }
+            try!(self.build_field(w, parent_query, field));
+        }
+        Ok(())
+    }
+
+    /// a new sql fragment for this database, using the writing preferences of the query
+    fn new_sql_frag(&self, query: &Query) -> SqlFrag {
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword_case = query.keyword_case.clone();
+        w.reuse_params = query.reuse_params;
+        match query.tag {
+            Some(ref tag) => {
+                // the tag can not close the comment early
+                w.append(&format!("/* {} */", tag.replace("*/", "* /")));
+            }
+            None => (),
+        }
+        w
+    }
+
+    /// write the rows of a values table, the parameters are numbered across all the rows
+    fn write_value_rows(&self, w: &mut SqlFrag, query: &Query) -> Result<(), DbError> {
+        w.left_river("VALUES");
+        let mut do_comma = false;
+        for row in &query.value_rows {
+            if do_comma {
+                w.commasp();
+            } else {
+                do_comma = true;
+            }
+            w.append("(");
+            let mut do_comma = false;
+            for operand in row {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                try!(self.build_operand(w, query, operand));
+            }
+            w.append(")");
+        }
+        Ok(())
+    }
+
+    /// build the select statment from the query object
+    pub fn build_select(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        let mut w = self.new_sql_frag(query);
+        try!(self.write_select(&mut w, query));
+        Ok(w)
+    }
+
+    /// write the select statement into the sql fragment,
+    /// subqueries are written into the same fragment to continue the numbering of the parameters
+    fn write_select(&self, w: &mut SqlFrag, query: &Query) -> Result<(), DbError> {
+        if !query.alias_filters.is_empty() {
+            try!(self.write_select(w, &query.wrap_alias_filters()));
+            return Ok(());
+        }
+        if !query.value_rows.is_empty() {
+            try!(self.write_value_rows(w, query));
+            return Ok(());
+        }
+        // without a FROM, this is a scalar query such as `SELECT now()`
+        let selects_all = query.enumerated_fields.iter().any(|field| {
+            match field.operand {
+                Operand::ColumnName(ref column) => column.column == "*",
+                _ => false,
+            }
+        });
+        if query.from.is_none() && (query.enumerated_fields.is_empty() || selects_all) {
+            return Err(DbError::new("There should be table, query, function to select from, or \
+                                     fields to select"));
+        }
+
+        w.left_river("SELECT");
+        try!(self.build_enumerated_fields(w, query, &query.enumerated_fields)); //TODO: add support for column_sql, fields, functions
+
+        match query.from {
+            Some(ref field) => {
+                w.left_river("FROM");
+                if query.only && self.sql_options().contains(&SqlOption::SupportsInheritance) {
+                    w.keyword("ONLY ");
+                }
+                try!(self.build_field(w, query, field));
+                match query.sample {
+                    Some(ref sample) => try!(self.build_table_sample(w, sample)),
+                    None => (),
+                }
+            }
+            None => (),
+        }
+        if !query.joins.is_empty() {
+            for join in &query.joins {
+                match join.modifier {
+                    Some(ref modifier) => {
+                        match *modifier {
+                            Modifier::LEFT => w.right_river("LEFT "),
+                            Modifier::RIGHT => w.right_river("RIGHT "),
+                            Modifier::FULL => w.right_river("FULL "),
+                        };
+                    }
+                    None => (),
+                }
+                match join.join_type {
+                    Some(ref join_type) => {
+                        match *join_type {
+                            JoinType::CROSS => w.keyword("CROSS "),
+                            JoinType::INNER => w.keyword("INNER "),
+                            JoinType::OUTER => w.keyword("OUTER "),
+                        };
+                    }
+                    None => (),
+                }
+                w.keyword("JOIN ");
+                match join.subquery {
+                    Some(ref subquery) => {
+                        if join.lateral {
+                            if !self.sql_options().contains(&SqlOption::SupportsLateral) {
+                                return Err(DbError::new("LATERAL join is not supported in this \
+                                                         database"));
+                            }
+                            w.keyword("LATERAL ");
+                        }
+                        let mut subquery = subquery.clone();
+                        subquery.is_subquery = true;
+                        subquery.finalize();
+                        w.append("(");
+                        try!(self.write_select(w, &subquery));
+                        w.append(")");
+                        w.keyword(" AS ");
+                        w.append(&join.table_name.name);
+                    }
+                    None => {
+                        w.append(&join.table_name.complete_name());
+                    }
+                }
+                w.append(" ");
+                if join.column1.len() != join.column2.len() {
+                    return Err(DbError::new("There should be equal number of corresponding columns \
+                                             to join"));
+                }
+                // a lateral subquery is already filtered by the columns it refers to
+                if join.lateral && join.column1.is_empty() {
+                    w.right_river("ON ");
+                    w.keyword("TRUE ");
+                }
+                let mut cnt = 0;
+                let mut do_and = false;
+                for jc in &join.column1 {
+                    if do_and {
+                        w.right_river("AND ");
+                    } else {
+                        w.right_river("ON ");
+                        do_and = true;
+                    }
+                    w.column_name(jc);
+                    w.append(" = ");
+                    w.column_name(&join.column2[cnt]);
+                    w.append(" ");
+                    cnt += 1;
+                }
+            }
+        }
+
+        let filters = query.select_filters();
+        if !filters.is_empty() {
+            w.left_river("WHERE ");
+            try!(self.build_filters(w, query, &filters));
+        }
+
+        if !query.group_by.is_empty() {
+            w.left_river("GROUP BY ");
+            match query.grouping_set {
+                Some(GroupingSet::ROLLUP) => {
+                    if self.sql_options().contains(&SqlOption::SupportsGroupingSets) {
+                        w.keyword("ROLLUP (");
+                        try!(self.build_operands(w, query, &query.group_by));
+                        w.append(") ");
+                    } else if self.sql_options().contains(&SqlOption::SupportsWithRollup) {
+                        try!(self.build_operands(w, query, &query.group_by));
+                        w.keyword(" WITH ROLLUP ");
+                    } else {
+                        return Err(DbError::new("GROUP BY ROLLUP is not supported in this \
+                                                 database"));
+                    }
+                }
+                Some(GroupingSet::CUBE) => {
+                    if !self.sql_options().contains(&SqlOption::SupportsGroupingSets) {
+                        return Err(DbError::new("GROUP BY CUBE is not supported in this database"));
+                    }
+                    w.keyword("CUBE (");
+                    try!(self.build_operands(w, query, &query.group_by));
+                    w.append(") ");
+                }
+                None => {
+                    let mut do_comma = false;
+                    for operand in &query.group_by {
+                        if do_comma {
+                            w.comma();
+                        } else {
+                            do_comma = true;
+                        }
+                        try!(self.build_operand(w, query, operand));
+                        w.append(" ");
+                    }
+                }
+            }
+        }
+
+        if !query.having.is_empty() {
+            w.left_river("HAVING ");
+            try!(self.build_filters(w, query, &query.having));
+        }
+
+        // a number refers to the position of the selected field, ie: `ORDER BY 2 DESC`
+        for &(ref column, _) in &query.order_by {
+            match column.parse::<usize>() {
+                Ok(position) => {
+                    if position < 1 ||
+                       (!selects_all && position > query.enumerated_fields.len()) {
+                        return Err(DbError::from_string(format!("ORDER BY {} is not a position \
+                                                                 of the selected fields",
+                                                                position)));
+                    }
+                }
+                Err(_) => (),
+            }
+        }
+        self.build_order_by(w, query);
+
+        match query.page_size {
+            Some(page_size) => {
+                w.left_river("LIMIT ");
+                w.append(&format!("{}", page_size));
+            }
+            None => (),
+        }
+
+        match query.page {
+            Some(page) => {
+                w.left_river("OFFSET ");
+                if !query.page_size.is_some() {
+                    return Err(DbError::new("Page size should be specified when paging"));
+                }
+                let page_size = query.page_size.unwrap();
+                let offset = page * page_size;
+                w.append(&format!("{}", offset));
+            }
+            None => (),
+        }
+
+        match query.lock {
+            Some(ref lock) => {
+                if !self.sql_options().contains(&SqlOption::SupportsRowLock) {
+                    return Err(DbError::new("Locking the selected rows is not supported in this \
+                                             database"));
+                }
+                if *lock == LockMode::NO_KEY_UPDATE || *lock == LockMode::KEY_SHARE {
+                    if !self.sql_options().contains(&SqlOption::SupportsKeyLock) {
+                        return Err(DbError::from_string(format!("{:?} lock is not supported \
+                                                                 in this database",
+                                                                lock)));
+                    }
+                }
+                w.left_river("FOR");
+                match *lock {
+                    LockMode::UPDATE => w.keyword("UPDATE"),
+                    LockMode::NO_KEY_UPDATE => w.keyword("NO KEY UPDATE"),
+                    LockMode::SHARE => w.keyword("SHARE"),
+                    LockMode::KEY_SHARE => w.keyword("KEY SHARE"),
+                };
+                if query.nowait {
+                    w.keyword(" NOWAIT");
+                }
+            }
+            None => {
+                if query.nowait {
+                    return Err(DbError::new("NOWAIT should be used with a row lock"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// TODO complete this
+    pub fn build_insert(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        let mut w = self.new_sql_frag(query);
+        if query.replace {
+            if !self.sql_options().contains(&SqlOption::SupportsReplaceInto) {
+                return Err(DbError::new("REPLACE INTO is not supported in this database, use \
+                                         INSERT .. ON CONFLICT instead"));
+            }
+            w.left_river("REPLACE");
+        } else {
+            w.left_river("INSERT");
+        }
+        w.keyword("INTO ");
+        let into_table = query.get_from_table();
+        if !into_table.is_some() {
+            return Err(DbError::new("There should be table to insert to"));
+        }
+        if into_table.is_some() {
+            let table_name = into_table.unwrap();
+            if self.sql_options().contains(&SqlOption::UsesSchema) {
+                w.append(&table_name.complete_name());
+            } else {
+                w.append(&table_name.name);
+            }
+        }
+
+
+        if !query.column_values.is_empty() {
+            if !query.values.is_empty() {
+                return Err(DbError::new("values should be empty, when inserting the paired column \
+                                         values"));
+            }
+            let column_values: Vec<&(String, Operand)> =
+                query.column_values
+                     .iter()
+                     .filter(|&&(ref column, _)| {
+                         !query.excluded_columns.iter().any(|c| c.column == *column)
+                     })
+                     .collect();
+            w.append("( ");
+            let mut do_comma = false;
+            for &&(ref column, _) in &column_values {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.column_name(column);
+            }
+            w.append(" ) ");
+            w.left_river("VALUES");
+            w.append("(");
+            let mut do_comma = false;
+            for &&(_, ref value) in &column_values {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                try!(self.build_operand(&mut w, query, value));
+            }
+            w.append(") ");
+        } else if query.default_values {
+            if !query.values.is_empty() {
+                return Err(DbError::new("values should be empty, when inserting the default \
+                                         values"));
+            }
+            if self.sql_options().contains(&SqlOption::UsesEmptyValues) {
+                w.left_river("VALUES");
+                w.append("() ");
+            } else {
+                w.left_river("DEFAULT");
+                w.keyword("VALUES ");
+            }
+        } else {
+            w.append("( ");
+            try!(self.build_enumerated_fields(&mut w, query, &query.enumerated_fields)); //TODO: add support for column_sql, fields, functions
+            w.append(" ) ");
+            if query.values.is_empty() && query.value_rows.is_empty() {
+                return Err(DbError::new("values should not be empty, when inserting records"));
+            }
+        }
+        if !query.values.is_empty() {
+            w.left_river("VALUES");
+            w.append("(");
+            let mut do_comma = false;
+            for vo in &query.values {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                try!(self.build_operand(&mut w, query, vo));
+            }
+            w.append(") ");
+        } else if !query.value_rows.is_empty() {
+            try!(self.write_value_rows(&mut w, query));
+            w.append(" ");
+        }
+        match query.on_conflict {
+            Some(ref on_conflict) => try!(self.build_on_conflict(&mut w, on_conflict)),
+            None => (),
+        }
+        if !query.enumerated_returns.is_empty() || query.return_inserted {
+            if self.sql_options().contains(&SqlOption::SupportsReturningClause) {
+                w.left_river("RETURNING");
+                let mut do_comma = false;
+                for field in &query.enumerated_returns {
+                    if do_comma {
+                        w.commasp();
+                    } else {
+                        do_comma = true;
+                    }
+                    try!(self.build_field(&mut w, query, field));
+                }
+                // xmax is only set on the row version created by an update
+                if query.return_inserted {
+                    if !self.sql_options().contains(&SqlOption::SupportsXmax) {
+                        return Err(DbError::new("Telling an inserted record from an updated one is \
+                                                 not supported in this database"));
+                    }
+                    if do_comma {
+                        w.commasp();
+                    }
+                    w.append("(xmax = 0)");
+                    w.keyword(" AS ");
+                    w.append("inserted");
+                }
+            }
+        }
+        w.ln();
+        Ok(w)
+    }
+
+    /// `TABLESAMPLE BERNOULLI (1)`, after the table and its alias
+    fn build_table_sample(&self, w: &mut SqlFrag, sample: &TableSample) -> Result<(), DbError> {
+        if !self.sql_options().contains(&SqlOption::SupportsTableSample) {
+            return Err(DbError::new("TABLESAMPLE is not supported in this database"));
+        }
+        w.keyword(" TABLESAMPLE ");
+        match sample.method {
+            SampleMethod::BERNOULLI => w.keyword("BERNOULLI"),
+            SampleMethod::SYSTEM => w.keyword("SYSTEM"),
+        };
+        w.append(&format!(" ({})", sample.percent));
+        Ok(())
+    }
+
+    /// `ON CONFLICT (a) DO UPDATE SET b = EXCLUDED.b` or `ON CONFLICT (a) DO NOTHING`
+    fn build_on_conflict(&self, w: &mut SqlFrag, on_conflict: &OnConflict) -> Result<(), DbError> {
+        if !self.sql_options().contains(&SqlOption::SupportsOnConflict) {
+            return Err(DbError::new("ON CONFLICT is not supported in this database"));
+        }
+        if on_conflict.columns.is_empty() {
+            return Err(DbError::new("There should be columns to detect the conflict on"));
+        }
+        w.left_river("ON");
+        w.keyword("CONFLICT ");
+        w.append(&format!("({}) ", on_conflict.columns.join(", ")));
+        w.left_river("DO");
+        if on_conflict.update_columns.is_empty() {
+            w.keyword("NOTHING ");
+        } else {
+            w.keyword("UPDATE SET ");
+            let mut do_comma = false;
+            for column in &on_conflict.update_columns {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.append(column);
+                w.append(" = ");
+                w.keyword("EXCLUDED");
+                w.append(".");
+                w.append(column);
+            }
+            w.append(" ");
+        }
+        Ok(())
+    }
+
+
+    pub fn build_update(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        let mut w = self.new_sql_frag(query);
+        w.left_river("UPDATE ");
+        let from_table = query.get_from_table();
+        if !from_table.is_some() {
+            return Err(DbError::new("There should be table to update from"));
+        }
+        if from_table.is_some() {
+            w.append(&from_table.unwrap().complete_name());
+        }
+        let enumerated_columns = query.get_enumerated_columns();
+        let mut do_comma = false;
+        if !enumerated_columns.is_empty() {
+            w.left_river("SET ");
+        }
+        let mut column_index = 0;
+        for ec in &enumerated_columns {
+            if do_comma {
+                w.commasp();
+            } else {
+                do_comma = true;
+            }
+            w.column_name(&ec.column);
+            w.append(" = ");
+            try!(self.build_operand(&mut w, query, &query.values[column_index]));
+            column_index += 1;
+        }
+
+        if !query.filters.is_empty() {
+            w.left_river("WHERE ");
+            try!(self.build_filters(&mut w, query, &query.filters));
+        }
+        try!(self.build_modify_order_by(&mut w, query));
+        try!(self.build_modify_limit(&mut w, query));
+        if !query.enumerated_returns.is_empty() {
+            if self.sql_options().contains(&SqlOption::SupportsReturningClause) {
+                w.left_river("RETURNING ");
+                let mut do_comma = false;
+                for field in &query.enumerated_returns {
+                    if do_comma {
+                        w.commasp();
+                    } else {
+                        do_comma = true;
+                    }
+                    try!(self.build_field(&mut w, query, field));
+                }
+            }
+        }
+        Ok(w)
+    }
+
+    pub fn build_delete(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        let mut w = self.new_sql_frag(query);
+        w.left_river("DELETE FROM ");
+        let from_table = query.get_from_table();
+        if !from_table.is_some() {
+            return Err(DbError::new("There should be table to delete from"));
+        }
+        if from_table.is_some() {
+            w.append(&from_table.unwrap().complete_name());
+        }
+        if !query.filters.is_empty() {
+            w.left_river("WHERE ");
+            try!(self.build_filters(&mut w, query, &query.filters));
+        }
+        try!(self.build_modify_order_by(&mut w, query));
+        try!(self.build_modify_limit(&mut w, query));
+        Ok(w)
+    }
+
+    /// build the ORDER BY clause of the query
+    fn build_order_by(&self, w: &mut SqlFrag, query: &Query) {
+        if !query.order_by.is_empty() {
+            w.left_river("ORDER BY ");
+            let mut do_comma = false;
+            for &(ref column, ref direction) in &query.order_by {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.column_name(&column);
+                match query.order_by_collations.get(column) {
+                    Some(collation) => {
+                        w.keyword(" COLLATE ");
+                        // mysql quotes identifiers with backticks
+                        if self.sql_options().contains(&SqlOption::UsesBacktickQuote) {
+                            w.append(&format!("`{}`", collation));
+                        } else {
+                            w.append(&format!("\"{}\"", collation));
+                        }
+                    }
+                    None => (),
+                }
+                match *direction {
+                    Direction::ASC => w.keyword(" ASC"),
+                    Direction::DESC => w.keyword(" DESC"),
+                };
+            }
+        }
+    }
+
+    /// ORDER BY on update and delete, for a deterministic order of the affected rows
+    fn build_modify_order_by(&self, w: &mut SqlFrag, query: &Query) -> Result<(), DbError> {
+        if !query.order_by.is_empty() {
+            if !self.sql_options().contains(&SqlOption::SupportsModifyOrderBy) {
+                return Err(DbError::new("ORDER BY on UPDATE and DELETE is not supported in this \
+                                         database"));
+            }
+            self.build_order_by(w, query);
+        }
+        Ok(())
+    }
+
+    /// LIMIT on update and delete, used in batched operations
+    fn build_modify_limit(&self, w: &mut SqlFrag, query: &Query) -> Result<(), DbError> {
+        match query.page_size {
+            Some(page_size) => {
+                if !self.sql_options().contains(&SqlOption::SupportsModifyLimit) {
+                    return Err(DbError::new("LIMIT on UPDATE and DELETE is not supported in this \
+                                             database"));
+                }
+                w.left_river("LIMIT ");
+                w.append(&format!("{}", page_size));
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// the name of the table, including the schema when the database uses it
+    fn table_name(&self, schema: &str, table: &str) -> String {
+        if self.sql_options().contains(&SqlOption::UsesSchema) && !schema.is_empty() {
+            format!("{}.{}", schema, table)
+        } else {
+            table.to_string()
+        }
+    }
+
+    /// write the collation of a column definition, ie: `COLLATE "en_US"`,
+    /// mysql expects the collation name unquoted
+    pub fn build_collation(&self, w: &mut SqlFrag, collation: &str) {
+        w.keyword(" COLLATE ");
+        if self.sql_options().contains(&SqlOption::UsesBacktickQuote) {
+            w.append(collation);
+        } else {
+            w.append(&format!("\"{}\"", collation));
+        }
+    }
+
+    /// build the foreign key constraint clause,
+    /// ie: `CONSTRAINT fk_name FOREIGN KEY (a, b) REFERENCES t (x, y)`
+    pub fn build_foreign_key(&self, w: &mut SqlFrag, foreign_key: &ForeignKey) {
+        match foreign_key.name {
+            Some(ref name) => {
+                w.keyword("CONSTRAINT ");
+                w.append(name);
+                w.sp();
+            }
+            None => (),
+        }
+        w.keyword("FOREIGN KEY ");
+        w.append(&format!("({})", foreign_key.columns.join(", ")));
+        w.keyword(" REFERENCES ");
+        w.append(&self.table_name(&foreign_key.foreign_schema, &foreign_key.foreign_table));
+        w.append(&format!(" ({})", foreign_key.foreign_columns.join(", ")));
+        match foreign_key.on_delete {
+            Some(ref action) => {
+                w.keyword(" ON DELETE ");
+                w.keyword(action.to_sql());
+            }
+            None => (),
+        }
+        match foreign_key.on_update {
+            Some(ref action) => {
+                w.keyword(" ON UPDATE ");
+                w.keyword(action.to_sql());
+            }
+            None => (),
+        }
+        if foreign_key.deferrable && self.sql_options().contains(&SqlOption::SupportsDeferrable) {
+            w.keyword(" DEFERRABLE INITIALLY DEFERRED");
+        }
+    }
+
+    /// build a single statement updating many records with different values,
+    /// each row has the value of the key column followed by the values of the columns.
+    /// ie: `UPDATE t SET x = v.x FROM (VALUES (..), (..)) AS v(id, x) WHERE t.id = v.id`,
+    /// databases that can't update from a VALUES list use
+    /// `UPDATE t SET x = CASE id WHEN .. THEN .. END WHERE id IN (..)`
+    pub fn build_bulk_update(&self,
+                             table: &Table,
+                             key: &str,
+                             columns: Vec<&str>,
+                             rows: &Vec<Vec<Value>>)
+                             -> Result<SqlFrag, DbError> {
+        if columns.is_empty() {
+            return Err(DbError::new("There should be columns to update"));
+        }
+        if rows.is_empty() {
+            return Err(DbError::new("There should be rows to update"));
+        }
+        for row in rows {
+            if row.len() != columns.len() + 1 {
+                return Err(DbError::new("Each row should have the key followed by a value for each \
+                                         of the columns"));
+            }
+        }
+        let mut w = SqlFrag::new(self.sql_options());
+        w.left_river("UPDATE");
+        w.append(&self.table_name(&table.schema, &table.name));
+        if self.sql_options().contains(&SqlOption::SupportsUpdateFrom) {
+            w.left_river("SET");
+            let mut do_comma = false;
+            for column in &columns {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.append(&format!("{} = v.{}", column, column));
+            }
+            // the values are cast, otherwise their types are unknown in a VALUES list
+            let mut names = vec![key];
+            names.extend(columns.iter().cloned());
+            let mut types = vec![];
+            for name in &names {
+                match table.get_column(name) {
+                    Some(column) => types.push(column.db_data_type),
+                    None => return Err(DbError::from_string(format!("There is no column {} in {}",
+                                                                    name, table.name))),
+                }
+            }
+            w.left_river("FROM");
+            w.append("(");
+            w.keyword("VALUES ");
+            let mut do_comma = false;
+            for row in rows {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.append("(");
+                for (index, value) in row.iter().enumerate() {
+                    if index > 0 {
+                        w.commasp();
+                    }
+                    w.keyword("CAST(");
+                    w.parameter(value.clone());
+                    w.keyword("AS ");
+                    w.append(&types[index]);
+                    w.append(")");
+                }
+                w.append(")");
+            }
+            w.append(")");
+            w.keyword(" AS ");
+            w.append(&format!("v({})", names.join(", ")));
+            w.left_river("WHERE");
+            w.append(&format!("{}.{} = v.{}", table.name, key, key));
+        } else {
+            w.left_river("SET");
+            let mut do_comma = false;
+            for (index, column) in columns.iter().enumerate() {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.append(&format!("{} = ", column));
+                w.keyword("CASE ");
+                w.append(key);
+                for row in rows {
+                    w.keyword(" WHEN ");
+                    w.parameter(row[0].clone());
+                    w.keyword(" THEN ");
+                    w.parameter(row[index + 1].clone());
+                }
+                w.keyword(" END");
+            }
+            w.left_river("WHERE");
+            w.append(key);
+            w.keyword(" IN ");
+            w.append("(");
+            let mut do_comma = false;
+            for row in rows {
+                if do_comma {
+                    w.commasp();
+                } else {
+                    do_comma = true;
+                }
+                w.parameter(row[0].clone());
+            }
+            w.append(")");
+        }
+        Ok(w)
+    }
+
+    /// build the statement that adds the foreign key constraint to the table
+    pub fn build_add_foreign_key(&self, table: &Table, foreign_key: &ForeignKey) -> SqlFrag {
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword("ALTER TABLE ");
+        w.append(&self.table_name(&table.schema, &table.name));
+        w.keyword(" ADD ");
+        self.build_foreign_key(&mut w, foreign_key);
+        w
+    }
+
+    /// build the statement that sets the comment of the table,
+    /// ie: `COMMENT ON TABLE t IS '..'` or `ALTER TABLE t COMMENT = '..'` in mysql
+    pub fn build_comment_on_table(&self, table: &Table, comment: &str) -> Result<SqlFrag, DbError> {
+        let mut w = SqlFrag::new(self.sql_options());
+        let table_name = self.table_name(&table.schema, &table.name);
+        if self.sql_options().contains(&SqlOption::UsesInlineComment) {
+            w.keyword("ALTER TABLE ");
+            w.append(&table_name);
+            w.keyword(" COMMENT = ");
+        } else {
+            if !self.sql_options().contains(&SqlOption::SupportsCommentOn) {
+                return Err(DbError::new("Comments are not supported in this database"));
+            }
+            w.keyword("COMMENT ON TABLE ");
+            w.append(&table_name);
+            w.keyword(" IS ");
+        }
+        self.build_comment(&mut w, comment);
+        Ok(w)
+    }
+
+    /// build the statement that sets the comment of the column,
+    /// ie: `COMMENT ON COLUMN t.c IS '..'`.
+    /// mysql can only set it by redefining the column, using the database type of the column
+    /// and keeping its NOT NULL and DEFAULT, ie: `ALTER TABLE t MODIFY COLUMN c text NOT NULL COMMENT '..'`
+    pub fn build_comment_on_column(&self,
+                                   table: &Table,
+                                   column: &Column,
+                                   comment: &str)
+                                   -> Result<SqlFrag, DbError> {
+        let mut w = SqlFrag::new(self.sql_options());
+        let table_name = self.table_name(&table.schema, &table.name);
+        if self.sql_options().contains(&SqlOption::UsesInlineComment) {
+            w.keyword("ALTER TABLE ");
+            w.append(&table_name);
+            w.keyword(" MODIFY COLUMN ");
+            w.append(&column.name);
+            w.append(" ");
+            w.append(&column.db_data_type);
+            if column.not_null {
+                w.keyword(" NOT NULL");
+            }
+            match column.default {
+                Some(ref default) => {
+                    w.keyword(" DEFAULT ");
+                    w.append(default);
+                }
+                None => (),
+            }
+            w.keyword(" COMMENT ");
+        } else {
+            if !self.sql_options().contains(&SqlOption::SupportsCommentOn) {
+                return Err(DbError::new("Comments are not supported in this database"));
+            }
+            w.keyword("COMMENT ON COLUMN ");
+            w.append(&table_name);
+            w.append(".");
+            w.append(&column.name);
+            w.keyword(" IS ");
+        }
+        self.build_comment(&mut w, comment);
+        Ok(w)
+    }
+
+    /// write the comment as a string literal, mysql also treats the backslash as an escape
+    fn build_comment(&self, w: &mut SqlFrag, comment: &str) {
+        if self.sql_options().contains(&SqlOption::UsesInlineComment) {
+            w.string_literal(&comment.replace("\\", "\\\\"));
+        } else {
+            w.string_literal(comment);
+        }
+    }
+
+    /// build the statement that sets the timeout of the statements on the connection,
+    /// ie: `SET statement_timeout = 5000`, the default timeout is restored when there is no timeout
+    pub fn build_set_statement_timeout(&self,
+                                       timeout: Option<&Duration>)
+                                       -> Result<SqlFrag, DbError> {
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword("SET ");
+        w.append(try!(self.statement_timeout_variable()));
+        w.append(" = ");
+        match timeout {
+            Some(timeout) => {
+                w.append(&format!("{}", Self::timeout_millis(timeout)));
+            }
+            None => {
+                w.keyword("DEFAULT");
+            }
+        }
+        Ok(w)
+    }
+
+    /// build the statement that sets the statement timeout until the end of the current transaction,
+    /// ie: `SET LOCAL statement_timeout = 5000`
+    pub fn build_set_local_statement_timeout(&self,
+                                             timeout: &Duration)
+                                             -> Result<SqlFrag, DbError> {
+        let mut w = try!(self.set_local_statement_timeout());
+        w.append(&format!("{}", Self::timeout_millis(timeout)));
+        Ok(w)
+    }
+
+    /// build the query of the current statement timeout of the connection,
+    /// ie: `SELECT current_setting('statement_timeout') AS statement_timeout`
+    pub fn build_show_statement_timeout(&self) -> Result<SqlFrag, DbError> {
+        let mut w = SqlFrag::new(self.sql_options());
+        let variable = try!(self.statement_timeout_variable());
+        w.keyword("SELECT ");
+        if self.sql_options().contains(&SqlOption::UsesMaxExecutionTime) {
+            w.append("@@SESSION.");
+            w.append(variable);
+        } else {
+            w.append("current_setting('");
+            w.append(variable);
+            w.append("')");
+        }
+        w.keyword(" AS ");
+        w.append("statement_timeout");
+        Ok(w)
+    }
+
+    /// build the statement that sets the statement timeout back to the value
+    /// from `build_show_statement_timeout`, ie: `SET statement_timeout = '5s'`
+    pub fn build_restore_statement_timeout(&self, previous: &str) -> Result<SqlFrag, DbError> {
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword("SET ");
+        w.append(try!(self.statement_timeout_variable()));
+        w.append(" = ");
+        // the postgresql setting may have a unit
+        if self.sql_options().contains(&SqlOption::UsesMaxExecutionTime) {
+            w.append(previous);
+        } else {
+            w.string_literal(previous);
+        }
+        Ok(w)
+    }
+
+    /// build the statement that sets the statement timeout back to the value
+    /// from `build_show_statement_timeout` until the end of the current transaction,
+    /// ie: `SET LOCAL statement_timeout = '5s'`
+    pub fn build_restore_local_statement_timeout(&self,
+                                                 previous: &str)
+                                                 -> Result<SqlFrag, DbError> {
+        let mut w = try!(self.set_local_statement_timeout());
+        w.string_literal(previous);
+        Ok(w)
+    }
+
+    /// only postgresql can scope the setting to the transaction
+    fn set_local_statement_timeout(&self) -> Result<SqlFrag, DbError> {
+        if !self.sql_options().contains(&SqlOption::SupportsStatementTimeout) {
+            return Err(DbError::new("Local statement timeout is not supported in this database"));
+        }
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword("SET LOCAL ");
+        w.append("statement_timeout");
+        w.append(" = ");
+        Ok(w)
+    }
+
+    fn statement_timeout_variable(&self) -> Result<&'static str, DbError> {
+        if self.sql_options().contains(&SqlOption::UsesMaxExecutionTime) {
+            Ok("max_execution_time")
+        } else if self.sql_options().contains(&SqlOption::SupportsStatementTimeout) {
+            Ok("statement_timeout")
+        } else {
+            Err(DbError::new("Statement timeout is not supported in this database"))
+        }
+    }
+
+    fn timeout_millis(timeout: &Duration) -> u64 {
+        timeout.as_secs() * 1000 + (timeout.subsec_nanos() / 1_000_000) as u64
+    }
+
+    /// build the statement that creates the index on the table,
+    /// ie: `CREATE UNIQUE INDEX idx_name ON schema.table (a, b) WHERE ..`
+    pub fn build_create_index(&self, table: &Table, index: &Index) -> Result<SqlFrag, DbError> {
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword("CREATE ");
+        if index.unique {
+            w.keyword("UNIQUE ");
+        }
+        w.keyword("INDEX ");
+        w.append(&index.name);
+        w.keyword(" ON ");
+        w.append(&self.table_name(&table.schema, &table.name));
+        w.append(" (");
+        try!(self.build_operands(&mut w, &Query::new(), &index.expressions));
+        w.append(")");
+        match index.predicate {
+            Some(ref predicate) => {
+                if !self.sql_options().contains(&SqlOption::SupportsPartialIndex) {
+                    return Err(DbError::new("Partial index is not supported in this database"));
+                }
+                w.keyword(" WHERE ");
+                w.append(predicate);
+            }
+            None => (),
+        }
+        Ok(w)
+    }
+
+    /// build the statement that creates the table from the result of the select query,
+    /// ie: `CREATE TABLE staging AS SELECT ..`, the values of the query are still parameters
+    pub fn build_create_table_as(&self, name: &str, query: &Query) -> Result<SqlFrag, DbError> {
+        match query.sql_type {
+            SqlType::SELECT => (),
+            _ => return Err(DbError::new("The query should be a select")),
+        }
+        let mut query = query.clone();
+        query.finalize();
+        let mut w = self.new_sql_frag(&query);
+        w.keyword("CREATE TABLE ");
+        w.append(name);
+        w.keyword(" AS");
+        try!(self.write_select(&mut w, &query));
+        Ok(w)
+    }
+
+    /// build the statement that defers the deferrable constraints in the current transaction
+    pub fn build_set_constraints_deferred(&self) -> Result<SqlFrag, DbError> {
+        if !self.sql_options().contains(&SqlOption::SupportsDeferrable) {
+            return Err(DbError::new("Deferred constraints is not supported in this database"));
+        }
+        let mut w = SqlFrag::new(self.sql_options());
+        w.keyword("SET CONSTRAINTS ALL DEFERRED");
+        Ok(w)
+    }
+}
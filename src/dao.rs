@@ -6,9 +6,15 @@ use chrono::naive::date::NaiveDate;
 use chrono::naive::time::NaiveTime;
 use chrono::naive::datetime::NaiveDateTime;
 use chrono::offset::utc::UTC;
+use chrono::offset::fixed::FixedOffset;
 use std::fmt;
+use std::{i32, i64};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::vec::IntoIter;
 use query::ColumnName;
 use table::IsTable;
+use database::DbError;
 use rustc_serialize::{Decodable, Encodable, Encoder, Decoder};
 use rustc_serialize::json::{self, ToJson, Json};
 
@@ -63,12 +69,92 @@ pub enum Value {
     Json(Json),
     Uuid(Uuid),
     DateTime(DateTime<UTC>),
+    /// a timestamp with time zone converted to a fixed offset, see `Postgres::set_time_zone`
+    DateTimeOffset(DateTime<FixedOffset>),
     NaiveDate(NaiveDate),
     NaiveTime(NaiveTime),
     NaiveDateTime(NaiveDateTime),
+    /// bound as a single array parameter, ie: `id = ANY($1)`
+    Array(Array),
+    /// bound as the minor units, ie: to a postgresql money or a bigint column
+    Money(Money),
     Null,
 }
 
+/// the elements of an array value, all of the same type
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum Array {
+    Bool(Vec<bool>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    F64(Vec<f64>),
+    String(Vec<String>),
+    Uuid(Vec<Uuid>),
+}
+
+impl Array{
+
+    /// panics when the values are not all of the same supported type
+    pub fn from_values(values: Vec<Value>) -> Self {
+        assert!(!values.is_empty(),
+                "The type of an empty array can not be determined");
+        let mut array = match values[0] {
+            Value::Bool(_) => Array::Bool(vec![]),
+            Value::I16(_) => Array::I16(vec![]),
+            Value::I32(_) => Array::I32(vec![]),
+            Value::I64(_) => Array::I64(vec![]),
+            Value::F64(_) => Array::F64(vec![]),
+            Value::String(_) => Array::String(vec![]),
+            Value::Uuid(_) => Array::Uuid(vec![]),
+            _ => panic!("unsupported array element {:?}", values[0]),
+        };
+        for value in values {
+            match (&mut array, value) {
+                (&mut Array::Bool(ref mut v), Value::Bool(x)) => v.push(x),
+                (&mut Array::I16(ref mut v), Value::I16(x)) => v.push(x),
+                (&mut Array::I32(ref mut v), Value::I32(x)) => v.push(x),
+                (&mut Array::I64(ref mut v), Value::I64(x)) => v.push(x),
+                (&mut Array::F64(ref mut v), Value::F64(x)) => v.push(x),
+                (&mut Array::String(ref mut v), Value::String(x)) => v.push(x),
+                (&mut Array::Uuid(ref mut v), Value::Uuid(x)) => v.push(x),
+                (_, value) => panic!("array elements should be of the same type, found {:?}", value),
+            }
+        }
+        array
+    }
+
+    pub fn to_values(&self) -> Vec<Value> {
+        match *self {
+            Array::Bool(ref v) => v.iter().map(|x| Value::Bool(*x)).collect(),
+            Array::I16(ref v) => v.iter().map(|x| Value::I16(*x)).collect(),
+            Array::I32(ref v) => v.iter().map(|x| Value::I32(*x)).collect(),
+            Array::I64(ref v) => v.iter().map(|x| Value::I64(*x)).collect(),
+            Array::F64(ref v) => v.iter().map(|x| Value::F64(*x)).collect(),
+            Array::String(ref v) => v.iter().map(|x| Value::String(x.clone())).collect(),
+            Array::Uuid(ref v) => v.iter().map(|x| Value::Uuid(*x)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match *self {
+            Array::Bool(ref v) => v.len(),
+            Array::I16(ref v) => v.len(),
+            Array::I32(ref v) => v.len(),
+            Array::I64(ref v) => v.len(),
+            Array::F64(ref v) => v.len(),
+            Array::String(ref v) => v.len(),
+            Array::Uuid(ref v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 
 /// custom implementation for value encoding to json,
 /// does not include unnecessary enum variants fields.
@@ -94,11 +180,14 @@ impl Encodable for Value{
                 println!("encoding date time: {}", x.to_rfc3339());
                 x.to_rfc3339().encode(s)
             }
+            Value::DateTimeOffset(ref x) => x.to_rfc3339().encode(s),
             Value::NaiveDate(ref x) => x.encode(s),
             Value::NaiveTime(ref x) => x.encode(s),
             Value::NaiveDateTime(ref x) => x.encode(s),
             Value::Object(ref x) => x.encode(s),
             Value::Json(ref x) => x.encode(s),
+            Value::Array(ref x) => x.to_values().encode(s),
+            Value::Money(ref x) => x.to_decimal_string().encode(s),
             Value::Null => s.emit_nil(),
         }
     }
@@ -122,11 +211,14 @@ impl ToJson for Value{
             Value::VecU8(ref x) => x.to_json(),
             Value::Uuid(ref x) => x.to_hyphenated_string().to_json(),
             Value::DateTime(ref x) => x.to_rfc3339().to_json(),
+            Value::DateTimeOffset(ref x) => x.to_rfc3339().to_json(),
             //            Value::NaiveDate(ref x) => x.to_json(),
             //            Value::NaiveTime(ref x) => x.to_json(),
-            //            Value::NaiveDateTime(ref x) => x.to_json(),
+            Value::NaiveDateTime(ref x) => format!("{:?}", x).to_json(), // ISO 8601, ie: 2016-03-01T10:30:00
             Value::Object(ref x) => x.to_json(),
             Value::Json(ref x) => x.clone(),
+            Value::Array(ref x) => x.to_values().to_json(),
+            Value::Money(ref x) => x.to_decimal_string().to_json(),
             Value::Null => Json::Null,
             _ => panic!("unsupported/unexpected type! {:?}", self),
         }
@@ -151,11 +243,14 @@ impl fmt::Display for Value{
             Value::VecU8(ref x) => write!(f, "'{:?}'", x),
             Value::Uuid(ref x) => write!(f, "'{}'", x),
             Value::DateTime(ref x) => write!(f, "'{}'", x),
+            Value::DateTimeOffset(ref x) => write!(f, "'{}'", x),
             Value::NaiveDate(ref x) => write!(f, "'{}'", x),
             Value::NaiveTime(ref x) => write!(f, "'{}'", x),
             Value::NaiveDateTime(ref x) => write!(f, "'{}'", x),
             Value::Object(ref x) => write!(f, "'{:?}'", x),
             Value::Json(ref x) => write!(f, "'{:?}'", x),
+            Value::Array(ref x) => write!(f, "'{:?}'", x.to_values()),
+            Value::Money(ref x) => write!(f, "'{}'", x),
             Value::Null => write!(f, "'nil'"),
             _ => panic!("unsupported/unexpected type! {:?}", self),
         }
@@ -209,6 +304,27 @@ pub struct DaoResult {
     pub page_size: Option<usize>,
 }
 
+/// what to do when more than one record has the same key, when casting to a map
+#[derive(Debug, Clone, PartialEq)]
+pub enum DuplicateKey {
+    /// fail with an error
+    Error,
+    /// the later record replaces the earlier one
+    LastWins,
+}
+
+/// a page of records casted to its struct, with the paging info
+#[derive(Debug)]
+pub struct PagedResult<T> {
+    pub data: Vec<T>,
+    /// the total number of records, regardless of paging
+    pub total: usize,
+    /// page of the query
+    pub page: Option<usize>,
+    /// page size
+    pub page_size: Option<usize>,
+}
+
 /// a serializable array of dao to be serialized to json request
 #[derive(RustcEncodable)]
 pub struct SerDaoResult {
@@ -268,6 +384,47 @@ impl DaoResult{
         obj
     }
 
+    /// cast the dao to the specific struct instance lazily, one record at a time
+    pub fn cast_iter<T: IsTable + IsDao>(self) -> CastIter<T> {
+        let table = T::table();
+        let renamed_columns = self.get_renamed_columns(&table.name);
+        CastIter {
+            dao: self.dao.into_iter(),
+            renamed_columns: renamed_columns,
+            non_nulls: table.non_nullable_columns(),
+            casted: PhantomData,
+        }
+    }
+
+    /// cast the dao to the specific struct instance, keyed by the value of the column
+    /// records with null on non nullable columns are skipped, the same as `cast`
+    pub fn cast_map<K, T>(&self,
+                          key: &str,
+                          on_duplicate: DuplicateKey)
+                          -> Result<HashMap<K, T>, DbError>
+        where K: FromValue + Eq + Hash + fmt::Debug,
+              T: IsTable + IsDao
+    {
+        let table = T::table();
+        let non_nulls = table.non_nullable_columns();
+        let renamed_columns = self.get_renamed_columns(&table.name);
+        let mut map = HashMap::new();
+        for dao in &self.dao {
+            let mut dao_clone = dao.clone();
+            dao_clone.correct_renamed_columns(&renamed_columns);
+            if dao_clone.all_has_values(&non_nulls) {
+                let k: K = dao_clone.get(key);
+                if on_duplicate == DuplicateKey::Error && map.contains_key(&k) {
+                    return Err(DbError::from_string(format!("Duplicate key {:?} in column {}",
+                                                            k,
+                                                            key)));
+                }
+                map.insert(k, T::from_dao(&dao_clone));
+            }
+        }
+        Ok(map)
+    }
+
     pub fn cast_one<T: IsTable + IsDao>(&self) -> Option<T> {
         let mut casted = self.cast::<T>();
         if casted.len() < 1 {
@@ -278,6 +435,29 @@ impl DaoResult{
     }
 }
 
+/// iterates over the records of a DaoResult, casting each dao as it is reached
+/// records with null on non nullable columns are skipped, the same as `cast`
+pub struct CastIter<T> {
+    dao: IntoIter<Dao>,
+    renamed_columns: Vec<(String, String)>,
+    non_nulls: Vec<String>,
+    casted: PhantomData<T>,
+}
+
+impl<T: IsTable + IsDao> Iterator for CastIter<T>{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while let Some(mut dao) = self.dao.next() {
+            dao.correct_renamed_columns(&self.renamed_columns);
+            if dao.all_has_values(&self.non_nulls) {
+                return Some(T::from_dao(&dao));
+            }
+        }
+        None
+    }
+}
+
 #[derive(Debug, Clone)]
 /// TODO: optimization, used enum types for the key values
 /// This will save allocation of string to enum keys which is a few bytes, int 
@@ -329,8 +509,33 @@ impl Dao{
     pub fn set_value(&mut self, column: &str, value: Value) {
         self.values.insert(column.to_string(), value);
     }
+    /// the key of the column, ignoring the case of the column when it is not found as is,
+    /// since the unquoted aliases are returned lowercased, ie: `UserId` is returned as `userid`
+    fn find_key(&self, column: &str) -> Option<String> {
+        if self.values.contains_key(column) {
+            return Some(column.to_string());
+        }
+        let keys: Vec<&String> = self.values
+                                     .keys()
+                                     .filter(|k| k.eq_ignore_ascii_case(column))
+                                     .collect();
+        // more than one would be ambiguous
+        if keys.len() == 1 {
+            Some(keys[0].to_string())
+        } else {
+            None
+        }
+    }
+
+    fn find(&self, column: &str) -> Option<&Value> {
+        match self.find_key(column) {
+            Some(key) => self.values.get(&key),
+            None => None,
+        }
+    }
+
     pub fn get_value(&self, column: &str) -> Value {
-        let value = self.values.get(column);
+        let value = self.find(column);
         match value {
             Some(value) => value.clone(),
             None => panic!("No such value for {}", column),
@@ -340,7 +545,8 @@ impl Dao{
     pub fn remove<T>(&mut self, column: &str) -> T
         where T: FromValue
     {
-        let value = self.values.remove(column).unwrap();
+        let key = self.find_key(column).unwrap();
+        let value = self.values.remove(&key).unwrap();
         FromValue::from_type(value)
     }
 
@@ -348,14 +554,25 @@ impl Dao{
     pub fn get<T>(&self, column: &str) -> T
         where T: FromValue
     {
-        let value = self.values.get(column).unwrap();
+        let value = self.find(column).unwrap();
         FromValue::from_type(value.clone())
     }
+    /// get the value, or the default of the type when the column is not selected or is NULL,
+    /// use this in `from_dao` for the fields which are not always selected
+    pub fn get_or_default<T>(&self, column: &str) -> T
+        where T: FromValue + Default
+    {
+        match self.get_opt(column) {
+            Some(value) => value,
+            None => T::default(),
+        }
+    }
+
     /// get optional value
     pub fn get_opt<T>(&self, column: &str) -> Option<T>
         where T: FromValue
     {
-        let value = self.values.get(column);
+        let value = self.find(column);
         if value.is_some() {
             let v = value.as_ref().unwrap().clone();
             match v {
@@ -367,9 +584,21 @@ impl Dao{
         }
     }
 
+    /// get the value, failing instead of losing precision or overflowing,
+    /// ie: a numeric with more digits than a f64 can hold, when read with
+    /// `Postgres::set_exact_numeric`
+    pub fn try_get<T>(&self, column: &str) -> Result<T, DbError>
+        where T: ExactFromValue
+    {
+        match self.find(column) {
+            Some(value) => T::from_value_exact(value),
+            None => Err(DbError::from_string(format!("No such value for {}", column))),
+        }
+    }
+
     /// get a reference of the type
     pub fn as_ref(&self, column: &str) -> &Value {
-        self.values.get(column).unwrap()
+        self.find(column).unwrap()
     }
 
 
@@ -504,6 +733,12 @@ impl ToValue for DateTime<UTC>{
     }
 }
 
+impl ToValue for DateTime<FixedOffset>{
+    fn to_db_type(&self) -> Value {
+        Value::DateTimeOffset(self.clone())
+    }
+}
+
 impl ToValue for NaiveDate{
     fn to_db_type(&self) -> Value {
         Value::NaiveDate(self.clone())
@@ -620,10 +855,18 @@ impl FromValue for f32{
         }
     }
 }
+/// also the decimal text of the numeric columns read with `Postgres::set_exact_numeric`,
+/// use `ExactFromValue` to not lose the digits past the precision of f64
 impl FromValue for f64{
     fn from_type(ty: Value) -> Self {
         match ty {
             Value::F64(x) => x,
+            Value::String(ref x) => {
+                match x.trim().parse() {
+                    Ok(x) => x,
+                    Err(_) => panic!("error! {:?}", ty),
+                }
+            }
             _ => panic!("error! {:?}", ty),
         }
     }
@@ -651,6 +894,18 @@ impl FromValue for DateTime<UTC>{
     fn from_type(ty: Value) -> Self {
         match ty {
             Value::DateTime(x) => x,
+            // the same instant, read with a time zone set
+            Value::DateTimeOffset(x) => x.with_timezone(&UTC),
+            _ => panic!("error!"),
+        }
+    }
+}
+
+impl FromValue for DateTime<FixedOffset>{
+    fn from_type(ty: Value) -> Self {
+        match ty {
+            Value::DateTimeOffset(x) => x,
+            Value::DateTime(x) => x.with_timezone(&FixedOffset::east(0)),
             _ => panic!("error!"),
         }
     }
@@ -683,6 +938,293 @@ impl FromValue for NaiveDateTime{
     }
 }
 
+/// strict conversion of the values, used in `Dao::try_get`,
+/// fails on a value of another type or instead of losing precision or overflowing
+pub trait ExactFromValue: Sized{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError>;
+}
+
+fn inexact(value: &Value, rust_type: &str) -> DbError {
+    DbError::from_string(format!("{:?} can not be represented exactly as {}", value, rust_type))
+}
+
+/// the decimal text has the same digits as the shortest rendering of the f64,
+/// ie: `0.10` is `0.1` but `0.12345678901234567890` is rounded to `0.12345678901234568`
+fn is_exact_decimal(text: &str, x: f64) -> bool {
+    let text = text.trim().trim_left_matches('+');
+    let (sign, digits) = if text.starts_with('-') {
+        ("-", &text[1..])
+    } else {
+        ("", text)
+    };
+    let digits = if digits.contains('.') {
+        digits.trim_right_matches('0').trim_right_matches('.')
+    } else {
+        digits
+    };
+    let digits = digits.trim_left_matches('0');
+    let digits = if digits.is_empty() || digits.starts_with('.') {
+        format!("0{}", digits)
+    } else {
+        digits.to_string()
+    };
+    let normalized = if digits == "0" {
+        digits
+    } else {
+        format!("{}{}", sign, digits)
+    };
+    normalized == format!("{}", x)
+}
+
+impl ExactFromValue for i32{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::I8(x) => Ok(x as i32),
+            Value::I16(x) => Ok(x as i32),
+            Value::I32(x) => Ok(x),
+            Value::I64(x) if x >= i32::MIN as i64 && x <= i32::MAX as i64 => Ok(x as i32),
+            Value::F64(x) if x.fract() == 0.0 && x >= i32::MIN as f64 && x <= i32::MAX as f64 => {
+                Ok(x as i32)
+            }
+            // mysql reads every value as text
+            Value::String(ref x) => x.trim().parse().map_err(|_| inexact(value, "i32")),
+            _ => Err(inexact(value, "i32")),
+        }
+    }
+}
+
+impl ExactFromValue for i64{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::I8(x) => Ok(x as i64),
+            Value::I16(x) => Ok(x as i64),
+            Value::I32(x) => Ok(x as i64),
+            Value::I64(x) => Ok(x),
+            // i64::MAX as f64 is rounded up to 2^63, which is out of range
+            Value::F64(x) if x.fract() == 0.0 && x >= i64::MIN as f64 && x < i64::MAX as f64 => {
+                Ok(x as i64)
+            }
+            Value::String(ref x) => x.trim().parse().map_err(|_| inexact(value, "i64")),
+            _ => Err(inexact(value, "i64")),
+        }
+    }
+}
+
+impl ExactFromValue for f32{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::F32(x) => Ok(x),
+            Value::F64(x) if (x as f32) as f64 == x => Ok(x as f32),
+            Value::I16(x) => Ok(x as f32),
+            // f32 has 24 bits of precision
+            Value::I32(x) if x >= -(1 << 24) && x <= 1 << 24 => Ok(x as f32),
+            _ => Err(inexact(value, "f32")),
+        }
+    }
+}
+
+impl ExactFromValue for f64{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::F32(x) => Ok(x as f64),
+            Value::F64(x) => Ok(x),
+            Value::I16(x) => Ok(x as f64),
+            Value::I32(x) => Ok(x as f64),
+            // f64 has 53 bits of precision
+            Value::I64(x) if x >= -(1 << 53) && x <= 1 << 53 => Ok(x as f64),
+            // the text of a numeric read exactly
+            Value::String(ref x) => {
+                match x.trim().parse::<f64>() {
+                    Ok(parsed) if is_exact_decimal(x, parsed) => Ok(parsed),
+                    _ => Err(inexact(value, "f64")),
+                }
+            }
+            _ => Err(inexact(value, "f64")),
+        }
+    }
+}
+
+impl ExactFromValue for bool{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::Bool(x) => Ok(x),
+            // the text of a mysql tinyint(1)
+            Value::String(ref x) => {
+                match x.trim() {
+                    "1" | "true" => Ok(true),
+                    "0" | "false" => Ok(false),
+                    _ => Err(inexact(value, "bool")),
+                }
+            }
+            _ => Err(inexact(value, "bool")),
+        }
+    }
+}
+
+impl ExactFromValue for String{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::String(ref x) => Ok(x.to_string()),
+            _ => Err(inexact(value, "String")),
+        }
+    }
+}
+
+impl ExactFromValue for Uuid{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::Uuid(x) => Ok(x),
+            Value::String(ref x) => Uuid::parse_str(x).map_err(|_| inexact(value, "Uuid")),
+            _ => Err(inexact(value, "Uuid")),
+        }
+    }
+}
+
+impl ExactFromValue for DateTime<UTC>{
+    fn from_value_exact(value: &Value) -> Result<Self, DbError> {
+        match *value {
+            Value::DateTime(x) => Ok(x),
+            Value::DateTimeOffset(x) => Ok(x.with_timezone(&UTC)),
+            _ => Err(inexact(value, "DateTime<UTC>")),
+        }
+    }
+}
+
+/// a monetary amount in the minor units of its currency, ie: cents,
+/// so the amounts are added and compared without the drift of f64.
+/// The amount is stored in a money or a bigint column, the currency is usually a column of its own
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Money {
+    /// ie: 1999 for 19.99 USD
+    pub amount: i64,
+    /// the ISO 4217 code, ie: USD
+    pub currency: String,
+}
+
+impl Money{
+
+    pub fn new(amount: i64, currency: &str) -> Self {
+        Money {
+            amount: amount,
+            currency: currency.to_string(),
+        }
+    }
+
+    /// the number of decimal places of the currency
+    pub fn minor_digits(currency: &str) -> u32 {
+        match currency {
+            "JPY" | "KRW" | "VND" | "CLP" | "ISK" => 0,
+            "BHD" | "KWD" | "OMR" | "JOD" | "TND" | "IQD" | "LYD" => 3,
+            _ => 2,
+        }
+    }
+
+    /// parse a decimal amount, ie: `1234.56`, the currency symbols and
+    /// group separators are ignored as in the postgresql money text, ie: `-$1,234.56`
+    pub fn parse(text: &str, currency: &str) -> Result<Self, DbError> {
+        let digits = Money::minor_digits(currency);
+        let negative = text.contains('-') || text.trim().starts_with('(');
+        let cleaned: String = text.chars().filter(|c| c.is_digit(10) || *c == '.').collect();
+        let splinters: Vec<&str> = cleaned.split('.').collect();
+        if cleaned.is_empty() || splinters.len() > 2 {
+            return Err(DbError::from_string(format!("Invalid amount {}", text)));
+        }
+        let fraction = if splinters.len() == 2 { splinters[1] } else { "" };
+        if fraction.len() > digits as usize {
+            return Err(DbError::from_string(format!("{} has more decimal places than {}",
+                                                    text,
+                                                    currency)));
+        }
+        let whole: i64 = match splinters[0] {
+            "" => 0,
+            whole => try!(whole.parse().map_err(|_| DbError::from_string(format!("Invalid amount {}", text)))),
+        };
+        let mut amount = whole;
+        for i in 0..digits as usize {
+            let digit = fraction.chars().nth(i).and_then(|c| c.to_digit(10)).unwrap_or(0);
+            amount = amount * 10 + digit as i64;
+        }
+        if negative {
+            amount = -amount;
+        }
+        Ok(Money::new(amount, currency))
+    }
+
+    /// the amount read from the database, which is either the minor units,
+    /// a decimal text or a f64 of a numeric column which is rounded to the minor units.
+    /// A money value already in another currency is an error
+    pub fn from_value(value: &Value, currency: &str) -> Result<Self, DbError> {
+        match *value {
+            Value::Money(ref money) => {
+                if money.currency == currency {
+                    Ok(money.clone())
+                } else {
+                    Err(DbError::from_string(format!("Unable to read {} as {}", money, currency)))
+                }
+            }
+            Value::I64(amount) => Ok(Money::new(amount, currency)),
+            Value::I32(amount) => Ok(Money::new(amount as i64, currency)),
+            Value::String(ref text) => Money::parse(text, currency),
+            Value::F64(amount) => {
+                let scale = 10i64.pow(Money::minor_digits(currency)) as f64;
+                Ok(Money::new((amount * scale).round() as i64, currency))
+            }
+            _ => Err(DbError::from_string(format!("Unable to read {:?} as money", value))),
+        }
+    }
+
+    /// the amount with the decimal places of the currency, ie: `19.99`
+    pub fn to_decimal_string(&self) -> String {
+        let digits = Money::minor_digits(&self.currency);
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let abs = self.amount.abs();
+        if digits == 0 {
+            return format!("{}{}", sign, abs);
+        }
+        let scale = 10i64.pow(digits);
+        format!("{}{}.{:0width$}",
+                sign,
+                abs / scale,
+                abs % scale,
+                width = digits as usize)
+    }
+}
+
+impl fmt::Display for Money{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.to_decimal_string(), self.currency)
+    }
+}
+
+/// stored as the minor units
+impl ToValue for Money{
+    fn to_db_type(&self) -> Value {
+        Value::Money(self.clone())
+    }
+}
+
+/// the currency of the amounts read without one, ie: a money column when
+/// `Postgres::set_money_currency` is not set, use `Money::from_value` for the others
+pub const DEFAULT_CURRENCY: &'static str = "USD";
+
+/// the values which carry their currency are read as is,
+/// the other amounts are in the `DEFAULT_CURRENCY`
+impl FromValue for Money{
+    fn from_type(ty: Value) -> Self {
+        match ty {
+            Value::Money(money) => money,
+            _ => {
+                match Money::from_value(&ty, DEFAULT_CURRENCY) {
+                    Ok(money) => money,
+                    Err(e) => panic!("error! {}", e),
+                }
+            }
+        }
+    }
+}
+
 
 
 #[test]
@@ -1,16 +1,17 @@
 use query::Query;
-use table::Table;
+use table::{Table, Column, Index};
 use dao::{Dao, DaoResult, Value};
 use writer::SqlFrag;
-use query::{Connector, Equality, Operand, Field};
-use query::{Direction, Modifier, JoinType};
-use query::{Filter, Condition};
+use builder::SqlBuilder;
 use query::SqlType;
 use std::error::Error;
 use std::fmt;
+use std::time::Duration;
 
 
 /// SqlOption, contains the info about the features and quirks of underlying database
+#[derive(Debug)]
+#[derive(Clone)]
 #[derive(PartialEq)]
 pub enum SqlOption {
     /// use the numbered parameters, as the case with rust-postgres
@@ -28,12 +29,130 @@ pub enum SqlOption {
     /// wheter the returned rows in a query included Meta columns for easy extraction of records
     /// (postgres returns this), sqlite does not return meta columns, so you have to extract it by index yourself.
     ReturnMetaColumns,
+    /// supports GROUP BY ROLLUP (..) and CUBE (..) (postgresql)
+    SupportsGroupingSets,
+    /// supports GROUP BY .. WITH ROLLUP (mysql)
+    SupportsWithRollup,
+    /// supports LIMIT on UPDATE and DELETE statements (mysql)
+    SupportsModifyLimit,
+    /// supports ORDER BY on UPDATE and DELETE statements (mysql)
+    SupportsModifyOrderBy,
+    /// quotes identifiers with backticks instead of double quotes (mysql)
+    UsesBacktickQuote,
+    /// intervals are written with a unit keyword, ie: `INTERVAL 3600 SECOND` (mysql)
+    UsesIntervalUnit,
+    /// the current timestamp is computed with modifiers, ie: `DATETIME('now', '-3600 seconds')` (sqlite)
+    UsesDatetimeModifier,
+    /// supports comparing to the rows of a subquery with ANY (..) and ALL (..) (postgresql, mysql)
+    SupportsAnyAll,
+    /// aggregates strings with `GROUP_CONCAT(x, sep)` instead of `STRING_AGG(x, sep)` (sqlite)
+    UsesGroupConcat,
+    /// aggregates strings with `GROUP_CONCAT(x SEPARATOR sep)` (mysql)
+    UsesGroupConcatSeparator,
+    /// supports `DEFERRABLE INITIALLY DEFERRED` constraints and `SET CONSTRAINTS` (postgresql)
+    SupportsDeferrable,
+    /// inserts a record of all defaults with `VALUES ()` instead of `DEFAULT VALUES` (mysql)
+    UsesEmptyValues,
+    /// supports joining other tables or a VALUES list on update, ie: `UPDATE t .. FROM ..` (postgresql)
+    SupportsUpdateFrom,
+    /// supports indexing only the rows matching a condition, ie: `CREATE INDEX .. WHERE ..` (postgresql, sqlite)
+    SupportsPartialIndex,
+    /// limits the running time of the statements with `statement_timeout` (postgresql)
+    SupportsStatementTimeout,
+    /// limits the running time of the statements with `max_execution_time` (mysql)
+    UsesMaxExecutionTime,
+    /// supports replacing the record with the same key with `REPLACE INTO` (mysql)
+    SupportsReplaceInto,
+    /// sets the comments with `COMMENT ON TABLE/COLUMN .. IS ..` (postgresql)
+    SupportsCommentOn,
+    /// sets the comments inline on the definition, ie: `ALTER TABLE t COMMENT = ..` (mysql)
+    UsesInlineComment,
+    /// compares NULL as a value with `<=>` instead of `IS DISTINCT FROM` (mysql)
+    UsesNullSafeEqual,
+    /// supports joining a subquery which refers to the preceding tables, ie: `JOIN LATERAL (..)` (postgresql, mysql 8)
+    SupportsLateral,
+    /// supports locking the selected rows, ie: `FOR UPDATE`, `FOR SHARE .. NOWAIT` (postgresql, mysql 8)
+    SupportsRowLock,
+    /// supports the weaker row locks `FOR NO KEY UPDATE` and `FOR KEY SHARE` (postgresql)
+    SupportsKeyLock,
+    /// supports upsert with `INSERT .. ON CONFLICT (..) DO UPDATE SET ..` (postgresql, sqlite)
+    SupportsOnConflict,
+    /// the rows have the system column `xmax`, which is 0 for a freshly inserted row (postgresql)
+    SupportsXmax,
+    /// binds a list of values as a single array parameter, ie: `id = ANY($1)` (postgresql)
+    SupportsArrayParam,
+    /// supports filtering the rows of an aggregate, ie: `COUNT(*) FILTER (WHERE ..)` (postgresql)
+    SupportsAggregateFilter,
+    /// supports selecting from a random sample of the table, ie: `TABLESAMPLE BERNOULLI (1)` (postgresql)
+    SupportsTableSample,
+    /// the key generated by the last insert of the connection can be selected,
+    /// ie: `LAST_INSERT_ID()` (mysql, sqlite)
+    SupportsLastInsertId,
+}
+
+/// the sql dialect of a database platform
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum Dialect {
+    Postgres,
+    Mysql,
+    Sqlite,
+}
+
+/// isolation level of a transaction
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel{
+
+    /// the sql name of the isolation level
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// classification of the errors from the database
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum DbErrorKind {
+    /// a unique or primary key constraint is violated, ie: duplicate key,
+    /// with the name of the constraint when the database tells it
+    UniqueViolation(Option<String>),
+    /// a foreign key constraint is violated
+    ForeignKeyViolation(Option<String>),
+    /// the transaction was aborted to resolve a deadlock
+    Deadlock,
+    /// the transaction could not be serialized with the concurrent transactions
+    SerializationFailure,
+    /// the statement or the wait for a lock took too long
+    Timeout,
+    /// any other error, with its description
+    Other(String),
 }
 
 #[derive(Debug)]
 pub struct DbError {
     description: String,
     cause: Option<String>,
+    /// the error code when the error came from the database,
+    /// SQLSTATE for postgresql, the error number for mysql
+    code: Option<String>,
+    /// the name of the violated constraint
+    constraint: Option<String>,
+    /// the classification when it is not told by the code, ie: sqlite errors
+    kind: Option<DbErrorKind>,
 }
 
 /// rough implementation of Database errors
@@ -42,6 +161,9 @@ impl DbError{
         DbError {
             description: description.to_string(),
             cause: None,
+            code: None,
+            constraint: None,
+            kind: None,
         }
     }
 
@@ -49,6 +171,85 @@ impl DbError{
         DbError {
             description: description,
             cause: None,
+            code: None,
+            constraint: None,
+            kind: None,
+        }
+    }
+
+    /// error from the database with its error code
+    pub fn with_code(description: String, code: &str) -> Self {
+        DbError {
+            description: description,
+            cause: None,
+            code: Some(code.to_string()),
+            constraint: None,
+            kind: None,
+        }
+    }
+
+    /// set the classification of this error, instead of deriving it from the code
+    pub fn with_kind(mut self, kind: DbErrorKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// set the error which happened while handling this error
+    pub fn with_cause(mut self, cause: String) -> Self {
+        self.cause = Some(cause);
+        self
+    }
+
+    /// set the name of the constraint that caused this error
+    pub fn with_constraint(mut self, constraint: Option<String>) -> Self {
+        self.constraint = constraint;
+        self
+    }
+
+    /// the name of the violated constraint, ie: `uq_users_email`
+    pub fn constraint(&self) -> Option<&str> {
+        match self.constraint {
+            Some(ref constraint) => Some(constraint),
+            None => None,
+        }
+    }
+
+    /// the error code of the database
+    pub fn code(&self) -> Option<&str> {
+        match self.code {
+            Some(ref code) => Some(code),
+            None => None,
+        }
+    }
+
+    /// classify the error using the postgresql SQLSTATE or the mysql error number,
+    /// the sqlite errors are classified when they are converted
+    pub fn kind(&self) -> DbErrorKind {
+        match self.kind {
+            Some(ref kind) => return kind.clone(),
+            None => (),
+        }
+        let constraint = self.constraint.clone();
+        match self.code() {
+            Some("23505") | Some("1062") => DbErrorKind::UniqueViolation(constraint),
+            Some("23503") | Some("1451") | Some("1452") => {
+                DbErrorKind::ForeignKeyViolation(constraint)
+            }
+            Some("40P01") | Some("1213") => DbErrorKind::Deadlock,
+            Some("40001") => DbErrorKind::SerializationFailure,
+            // query_canceled by statement_timeout, lock_not_available, lock wait timeout
+            // or max_execution_time exceeded
+            Some("57014") | Some("55P03") | Some("1205") | Some("3024") => DbErrorKind::Timeout,
+            _ => DbErrorKind::Other(self.description.clone()),
+        }
+    }
+
+    /// the transaction failed due to concurrent transactions,
+    /// retrying the whole transaction may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self.kind() {
+            DbErrorKind::SerializationFailure | DbErrorKind::Deadlock => true,
+            _ => false,
         }
     }
 }
@@ -65,7 +266,10 @@ impl Error for DbError{
 
 impl fmt::Display for DbError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.description())
+        match self.cause {
+            Some(ref cause) => write!(fmt, "{}, {}", self.description(), cause),
+            None => write!(fmt, "{}", self.description()),
+        }
     }
 }
 
@@ -83,18 +287,60 @@ pub trait Database{
     /// lower version of database has fewer supported features
     fn version(&self) -> String;
 
+    /// begin database transaction, panics when it can not be started, see `try_begin`
+    fn begin(&self) {
+        match self.try_begin() {
+            Ok(_) => (),
+            Err(e) => panic!("Unable to begin transaction {}", e),
+        }
+    }
+
     /// begin database transaction
-    fn begin(&self);
+    fn try_begin(&self) -> Result<(), DbError>;
+
+    /// begin a read only transaction, data changes executed within it will fail
+    fn begin_read_only(&self) -> Result<(), DbError>;
+
+    /// begin a transaction with the isolation level
+    fn begin_with_isolation(&self, level: IsolationLevel) -> Result<(), DbError>;
+
+    /// commit database transaction, panics when the commit fails, see `try_commit`
+    fn commit(&self) {
+        match self.try_commit() {
+            Ok(_) => (),
+            Err(e) => panic!("Unable to commit transaction {}", e),
+        }
+    }
+
+    /// commit database transaction, the commit of a serializable transaction
+    /// may fail with an error which is retryable
+    fn try_commit(&self) -> Result<(), DbError>;
 
-    /// commit database transaction
-    fn commit(&self);
+    /// rollback data changes executed prior to calling the begin method,
+    /// panics when the rollback fails, see `try_rollback`
+    fn rollback(&self) {
+        match self.try_rollback() {
+            Ok(_) => (),
+            Err(e) => panic!("Unable to rollback transaction {}", e),
+        }
+    }
 
     /// rollback data changes executed prior to calling the begin method
-    fn rollback(&self);
+    fn try_rollback(&self) -> Result<(), DbError>;
 
     /// determine if this transaction has been committed or rolledback
     fn is_transacted(&self) -> bool;
 
+    /// whether a transaction is open, started with begin or kept open with auto commit off
+    fn in_transaction(&self) -> bool;
+
+    /// auto commit is on by default, each statement is committed on its own.
+    /// When turned off, a transaction is started right away and again after each
+    /// commit or rollback, so data changes are only kept when explicitly committed,
+    /// calling begin is not needed then.
+    /// Turning auto commit back on commits the pending data changes
+    fn set_auto_commit(&self, auto_commit: bool) -> Result<(), DbError>;
+
     /// determine if the database connection closed
     fn is_closed(&self) -> bool;
 
@@ -120,7 +366,7 @@ pub trait Database{
     /// insert an object, returns the inserted Dao value
     /// including the value generated via the defaults
     fn insert(&self, query: &Query) -> Result<Dao, DbError> {
-        let sql_frag = self.build_insert(query);
+        let sql_frag = try!(self.build_insert(query));
         match self.execute_sql_with_one_return(&sql_frag.sql, &sql_frag.params) {
             Ok(Some(result)) => Ok(result),
             Ok(None) => Err(DbError::new("No result from insert")),
@@ -136,11 +382,87 @@ pub trait Database{
     /// returns the number of deleted records
     fn delete(&self, query: &Query) -> Result<usize, String>;
 
+    /// update the records without returning them
+    /// returns the number of updated records, which can be zero or many
+    fn execute_update_count(&self, query: &Query) -> Result<usize, DbError> {
+        match query.sql_type {
+            SqlType::UPDATE => (),
+            _ => panic!("The query should be an update"),
+        }
+        let mut query = query.clone();
+        query.enumerated_returns = vec![];
+        self.execute(&query)
+    }
+
+    /// update many records of the table with different values in a single statement,
+    /// each row has the value of the key column followed by the values of the columns
+    /// returns the number of updated records
+    fn bulk_update(&self,
+                   table: &Table,
+                   key: &str,
+                   columns: Vec<&str>,
+                   rows: &Vec<Vec<Value>>)
+                   -> Result<usize, DbError> {
+        let frag = try!(self.sql_builder().build_bulk_update(table, key, columns, rows));
+        self.execute_sql(&frag.sql, &frag.params)
+    }
+
+    /// defer the checking of all deferrable constraints until the current transaction commits,
+    /// needed when inserting records that refer to each other
+    fn set_constraints_deferred(&self) -> Result<(), DbError> {
+        if !self.sql_options().contains(&SqlOption::SupportsDeferrable) {
+            return Err(DbError::new("Deferred constraints is not supported in this database"));
+        }
+        let frag = try!(self.sql_builder().build_set_constraints_deferred());
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
+    /// the value generated for the auto increment key by the last insert of this connection,
+    /// used to retrieve the inserted record where RETURNING is not supported
+    fn last_insert_id(&self) -> Result<Value, DbError> {
+        Err(DbError::new("The key generated by the last insert is not available"))
+    }
+
+    /// cancel the statements of this connection which run longer than the timeout
+    fn set_statement_timeout(&self, timeout: &Duration) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_set_statement_timeout(Some(timeout)));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
+    /// restore the statement timeout of this connection to the default
+    fn reset_statement_timeout(&self) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_set_statement_timeout(None));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
+    /// the statement timeout of this connection as the database shows it, ie: `5s`, `0`
+    fn get_statement_timeout(&self) -> Result<String, DbError> {
+        let frag = try!(self.sql_builder().build_show_statement_timeout());
+        let dao = try!(self.execute_sql_with_one_return(&frag.sql, &vec![]));
+        match dao.map(|dao| dao.get_value("statement_timeout")) {
+            Some(Value::String(timeout)) => Ok(timeout),
+            Some(Value::I64(timeout)) => Ok(format!("{}", timeout)),
+            _ => Err(DbError::new("Unable to get the statement timeout")),
+        }
+    }
+
+    /// set the statement timeout of this connection back to a value from `get_statement_timeout`
+    fn restore_statement_timeout(&self, previous: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_restore_statement_timeout(previous));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
     /// execute query with return dao,
     /// use the enumerated column for data extraction when db doesn't support returning the records column names
     fn execute_with_return(&self, query: &Query) -> Result<DaoResult, DbError> {
-        let sql_frag = &self.build_query(query);
-        let result = try!(self.execute_sql_with_return(&sql_frag.sql, &sql_frag.params));
+        let sql_frag = &try!(self.build_query(query));
+        let result = try!(with_query_timeout(self, &query.timeout, || {
+            self.execute_sql_with_return(&sql_frag.sql, &sql_frag.params)
+        }));
         let dao_result = DaoResult {
             dao: result,
             renamed_columns: query.get_renamed_columns(),
@@ -153,14 +475,16 @@ pub trait Database{
 
     /// execute query with 1 return dao
     fn execute_with_one_return(&self, query: &Query) -> Result<Option<Dao>, DbError> {
-        let sql_frag = &self.build_query(query);
-        self.execute_sql_with_one_return(&sql_frag.sql, &sql_frag.params)
+        let sql_frag = &try!(self.build_query(query));
+        with_query_timeout(self, &query.timeout, || {
+            self.execute_sql_with_one_return(&sql_frag.sql, &sql_frag.params)
+        })
     }
 
     /// execute query with no return dao
     fn execute(&self, query: &Query) -> Result<usize, DbError> {
-        let sql_frag = &self.build_query(query);
-        self.execute_sql(&sql_frag.sql, &sql_frag.params)
+        let sql_frag = &try!(self.build_query(query));
+        with_query_timeout(self, &query.timeout, || self.execute_sql(&sql_frag.sql, &sql_frag.params))
     }
 
     /// execute insert with returning clause, update with returning clause
@@ -181,453 +505,95 @@ pub trait Database{
     /// everything else, no required return other than error or affected number of records
     fn execute_sql(&self, sql: &str, param: &Vec<Value>) -> Result<usize, DbError>;
 
-    /// build a query, return the sql string and the parameters.
-    /// use by select to build the select query
-    /// build all types of query
-    /// TODO: need to supply the number of parameters where to start the numbering of the number parameters
-    fn build_query(&self, query: &Query) -> SqlFrag {
-        match query.sql_type {
-            SqlType::SELECT => self.build_select(query),
-            SqlType::INSERT => self.build_insert(query),
-            SqlType::UPDATE => self.build_update(query),
-            SqlType::DELETE => self.build_delete(query),
-        }
-    }
+    /// execute several statements separated by semicolons in one call,
+    /// no parameters are allowed, this is intended for DDL and migration scripts
+    fn execute_batch(&self, sql: &str) -> Result<(), DbError>;
 
-    /// build operand, i.e: columns, query, function, values
-    fn build_operand(&self, w: &mut SqlFrag, parent_query: &Query, operand: &Operand) {
-        match *operand {
-            Operand::ColumnName(ref column_name) => {
-                if parent_query.joins.is_empty() {
-                    w.append(&column_name.column);
-                } else {
-                    w.append(&column_name.complete_name());
-                }
-            }
-            Operand::TableName(ref table_name) => {
-                if self.sql_options().contains(&SqlOption::UsesSchema) {
-                    w.append(&table_name.complete_name());
-                } else {
-                    w.append(&table_name.name);
-                }
-            }
-            Operand::Function(ref function) => {
-                w.append("(");
-                let mut do_comma = false;
-                for param in &function.params {
-                    if do_comma {
-                        w.commasp();
-                    } else {
-                        do_comma = true;
-                    }
-                    self.build_operand(w, parent_query, param);
-                }
-                w.append(")");
-            }
-            Operand::Query(ref q) => {
-                panic!("TODO: causes error Attributes 'readnone and readonly' are incompatible! \
-                        LLVM ERROR: Broken function found, compilation aborted!")
-                //let sql_frag = &self.build_query(&q);
-                //w.append(&sql_frag.sql);
-            }
-            Operand::Value(ref value) => {
-                w.parameter(value.clone());
-            }
-            Operand::Vec(ref operands) => {
-                let mut do_comma = false;
-                if !operands.is_empty() {
-                    w.append("(");
-                    for op in operands {
-                        println!("op: {:?}", op);
-                        if do_comma {
-                            w.commasp();
-                        } else {
-                            do_comma = true;
-                        }
-                        self.build_operand(w, parent_query, op);
-                    }
-                    w.append(")");
-                }
-            }
-        }
+    /// the sql builder for the dialect of this database
+    fn sql_builder(&self) -> SqlBuilder {
+        SqlBuilder::new(self.sql_options())
     }
 
-    fn build_condition(&self, w: &mut SqlFrag, parent_query: &Query, cond: &Condition) {
-        self.build_operand(w, parent_query, &cond.left);
-        w.append(" ");
-        match cond.equality {
-            Equality::EQ => {
-                w.append("= ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::NEQ => {
-                w.append("!= ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::LT => {
-                w.append("< ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::LTE => {
-                w.append("<= ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::GT => {
-                w.append("> ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::GTE => {
-                w.append(">= ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::IN => {
-                w.append("IN ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::NOT_IN => {
-                w.append("NOT IN ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::LIKE => {
-                w.append("LIKE ");
-                self.build_operand(w, parent_query, &cond.right);
-            }
-            Equality::IS_NOT_NULL => {
-                w.append("IS NOT NULL");
-            }
-
-            Equality::IS_NULL => {
-                w.append("IS NULL");
-            }
-        }
+    /// build a query, return the sql string and the parameters.
+    /// build all types of query
+    fn build_query(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        self.sql_builder().build_query(query)
     }
 
-    fn build_field(&self, w: &mut SqlFrag, parent_query: &Query, field: &Field) {
-        self.build_operand(w, parent_query, &field.operand);
-        match field.name {
-            Some(ref name) => {
-                w.append(" AS ");
-                w.append(name);
-            }
-            None => (),
-        }
+    /// build the select statment from the query object
+    fn build_select(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        self.sql_builder().build_select(query)
     }
 
-
-    fn build_filter(&self, w: &mut SqlFrag, parent_query: &Query, filter: &Filter) {
-        if !filter.subfilters.is_empty() {
-            w.append("( ");
-        }
-        self.build_condition(w, parent_query, &filter.condition);
-        for filt in &filter.subfilters {
-            match filt.connector {
-                Connector::And => {
-                    w.append("AND ");
-                }
-                Connector::Or => {
-                    w.append("OR ");
-                }
-            }
-            self.build_filter(w, parent_query, filt);// build sub filters as well
-        }
-        if !filter.subfilters.is_empty() {
-            w.append(" )");
-        }
+    /// build the insert statement from the query object
+    fn build_insert(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        self.sql_builder().build_insert(query)
     }
 
-    /// build the filter clause or the where clause of the query
-    /// TODO: add the sub filters
-    fn build_filters(&self, w: &mut SqlFrag, parent_query: &Query, filters: &Vec<Filter>) {
-        let mut do_and = false;
-        for filter in filters {
-            if do_and {
-                w.left_river("AND ");
-            } else {
-                do_and = true;
-            }
-            self.build_filter(w, parent_query, filter);
-        }
+    /// build the update statement from the query object
+    fn build_update(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        self.sql_builder().build_update(query)
     }
 
-    /// build the enumerated, distinct, *, columns
-    fn build_enumerated_fields(&self,
-                               w: &mut SqlFrag,
-                               parent_query: &Query,
-                               enumerated_fields: &Vec<Field>) {
-        let mut do_comma = false;
-        let mut cnt = 0;
-        for field in enumerated_fields {
-            if do_comma {
-                w.commasp();
-            } else {
-                do_comma = true;
-            }
-            cnt += 1;
-            if cnt % 4 == 0 {//break at every 4 columns to encourage sql tuning/revising
-                w.left_river("");
-            }
-            self.build_field(w, parent_query, field);
-        }
+    /// build the delete statement from the query object
+    fn build_delete(&self, query: &Query) -> Result<SqlFrag, DbError> {
+        self.sql_builder().build_delete(query)
     }
 
-    /// build the select statment from the query object
-    fn build_select(&self, query: &Query) -> SqlFrag {
-        let mut w = SqlFrag::new(self.sql_options());
-        w.left_river("SELECT");
-        self.build_enumerated_fields(&mut w, query, &query.enumerated_fields); //TODO: add support for column_sql, fields, functions
-        w.left_river("FROM");
-
-        assert!(query.from.is_some(),
-                "There should be table, query, function to select from");
-
-        match query.from {
-            Some(ref field) => {
-                self.build_field(&mut w, query, field);
-            }
-            None => println!("Warning: No from in this query"),
-        }
-        if !query.joins.is_empty() {
-            for join in &query.joins {
-                match join.modifier {
-                    Some(ref modifier) => {
-                        match *modifier {
-                            Modifier::LEFT => w.right_river("LEFT "),
-                            Modifier::RIGHT => w.right_river("RIGHT "),
-                            Modifier::FULL => w.right_river("FULL "),
-                        };
-                    }
-                    None => (),
-                }
-                match join.join_type {
-                    Some(ref join_type) => {
-                        match *join_type {
-                            JoinType::CROSS => w.append("CROSS "),
-                            JoinType::INNER => w.append("INNER "),
-                            JoinType::OUTER => w.append("OUTER "),
-                        };
-                    }
-                    None => (),
-                }
-                w.append("JOIN ");
-                w.append(&join.table_name.complete_name());
-                w.append(" ");
-                assert!(join.column1.len() == join.column2.len(),
-                        "There should be equal number of corresponding columns to join");
-                let mut cnt = 0;
-                let mut do_and = false;
-                for jc in &join.column1 {
-                    if do_and {
-                        w.right_river("AND ");
-                    } else {
-                        w.right_river("ON ");
-                        do_and = true;
-                    }
-                    w.append(jc);
-                    w.append(" = ");
-                    w.append(&join.column2[cnt]);
-                    w.append(" ");
-                    cnt += 1;
-                }
-            }
-        }
-
-        if !query.filters.is_empty() {
-            w.left_river("WHERE ");
-            self.build_filters(&mut w, query, &query.filters);
-        }
-
-        if !query.group_by.is_empty() {
-            w.left_river("GROUP BY ");
-            let mut do_comma = false;
-            for operand in &query.group_by {
-                if do_comma {
-                    w.comma();
-                } else {
-                    do_comma = true;
-                }
-                self.build_operand(&mut w, query, operand);
-                w.append(" ");
-            }
-        }
-
-        if !query.having.is_empty() {
-            w.left_river("HAVING ");
-            let mut do_comma = false;
-            for hav in &query.having {
-                if do_comma {
-                    w.commasp();
-                } else {
-                    do_comma = true;
-                }
-                self.build_condition(&mut w, query, hav);
-            }
-        }
-
-        if !query.order_by.is_empty() {
-            w.left_river("ORDER BY ");
-            let mut do_comma = false;
-            for &(ref column, ref direction) in &query.order_by {
-                if do_comma {
-                    w.commasp();
-                } else {
-                    do_comma = true;
-                }
-                w.append(&column);
-                match *direction {
-                    Direction::ASC => w.append(" ASC"),
-                    Direction::DESC => w.append(" DESC"),
-                };
-            }
-        }
-
-        match query.page_size {
-            Some(page_size) => {
-                w.left_river("LIMIT ");
-                w.append(&format!("{}", page_size));
-            }
-            None => (),
-        }
-
-        match query.page {
-            Some(page) => {
-                w.left_river("OFFSET ");
-                assert!(query.page_size.is_some(),
-                        "Page size should be specified when paging");
-                let page_size = query.page_size.unwrap();
-                let offset = page * page_size;
-                w.append(&format!("{}", offset));
-            }
-            None => (),
-        }
-        w
-    }
-
-    /// TODO complete this
-    fn build_insert(&self, query: &Query) -> SqlFrag {
-        println!("building insert query");
-        let mut w = SqlFrag::new(self.sql_options());
-        w.left_river("INSERT");
-        w.append("INTO ");
-        let into_table = query.get_from_table();
-        assert!(into_table.is_some(), "There should be table to insert to");
-        if into_table.is_some() {
-            let table_name = into_table.unwrap();
-            if self.sql_options().contains(&SqlOption::UsesSchema) {
-                w.append(&table_name.complete_name());
-            } else {
-                w.append(&table_name.name);
-            }
-        }
-
+    fn sql_options(&self) -> Vec<SqlOption>;
 
-        w.append("( ");
-        self.build_enumerated_fields(&mut w, query, &query.enumerated_fields); //TODO: add support for column_sql, fields, functions
-        w.append(" ) ");
-        assert!(!query.values.is_empty(),
-                "values should not be empty, when inserting records");
-        if !query.values.is_empty() {
-            w.left_river("VALUES");
-            w.append("(");
-            let mut do_comma = false;
-            for vo in &query.values {
-                if do_comma {
-                    w.commasp();
-                } else {
-                    do_comma = true;
-                }
-                self.build_operand(&mut w, query, vo);
-            }
-            w.append(") ");
-        }
-        if !query.enumerated_returns.is_empty() {
-            if self.sql_options().contains(&SqlOption::SupportsReturningClause) {
-                w.left_river("RETURNING");
-                let mut do_comma = false;
-                for field in &query.enumerated_returns {
-                    if do_comma {
-                        w.commasp();
-                    } else {
-                        do_comma = true;
-                    }
-                    self.build_field(&mut w, query, field);
-                }
-            }
+    /// the sql dialect of this database, for code that has to branch per database,
+    /// by default it is told from the sql options, so existing implementors need not add it
+    fn dialect(&self) -> Dialect {
+        let sql_options = self.sql_options();
+        if sql_options.contains(&SqlOption::UsesQuestionMark) {
+            Dialect::Mysql
+        } else if sql_options.contains(&SqlOption::UsesDatetimeModifier) {
+            Dialect::Sqlite
+        } else {
+            Dialect::Postgres
         }
-        w.ln();
-        w
     }
 
+}
 
-    fn build_update(&self, query: &Query) -> SqlFrag {
-        let mut w = SqlFrag::new(self.sql_options());
-        w.left_river("UPDATE ");
-        let from_table = query.get_from_table();
-        assert!(from_table.is_some(), "There should be table to update from");
-        if from_table.is_some() {
-            w.append(&from_table.unwrap().complete_name());
-        }
-        let enumerated_columns = query.get_enumerated_columns();
-        let mut do_comma = false;
-        if !enumerated_columns.is_empty() {
-            w.left_river("SET ");
-        }
-        let mut column_index = 0;
-        for ec in &enumerated_columns {
-            if do_comma {
-                w.commasp();
-            } else {
-                do_comma = true;
-            }
-            w.append(&ec.column);
-            w.append(" = ");
-            let value = &query.values[column_index];
-            match value {
-                &Operand::Value(ref value) => {
-                    w.parameter(value.clone());
-                }
-                _ => {}
-            }
-            column_index += 1;
-        }
-
-        if !query.filters.is_empty() {
-            w.left_river("WHERE ");
-            self.build_filters(&mut w, query, &query.filters);
-        }
-        if !query.enumerated_returns.is_empty() {
-            if self.sql_options().contains(&SqlOption::SupportsReturningClause) {
-                w.left_river("RETURNING ");
-                let mut do_comma = false;
-                for field in &query.enumerated_returns {
-                    if do_comma {
-                        w.commasp();
-                    } else {
-                        do_comma = true;
-                    }
-                    self.build_field(&mut w, query, field);
-                }
-            }
-        }
-        w
+/// run the statement with the timeout of the query, the timeout of the connection is kept.
+/// Inside a postgresql transaction it is set with `SET LOCAL`, so when the statement aborts
+/// the transaction the rollback also undoes the timeout.
+fn with_query_timeout<D, T, F>(db: &D, timeout: &Option<Duration>, run: F) -> Result<T, DbError>
+    where D: Database + ?Sized,
+          F: FnOnce() -> Result<T, DbError>
+{
+    let timeout = match *timeout {
+        Some(ref timeout) => timeout,
+        None => return run(),
+    };
+    let builder = db.sql_builder();
+    let local = db.in_transaction() &&
+                db.sql_options().contains(&SqlOption::SupportsStatementTimeout);
+    let previous = try!(db.get_statement_timeout());
+    let set = if local {
+        try!(builder.build_set_local_statement_timeout(timeout))
+    } else {
+        try!(builder.build_set_statement_timeout(Some(timeout)))
+    };
+    try!(db.execute_sql(&set.sql, &vec![]));
+    let result = run();
+    if local && result.is_err() {
+        return result;
     }
-
-    fn build_delete(&self, query: &Query) -> SqlFrag {
-        let mut w = SqlFrag::new(self.sql_options());
-        w.left_river("DELETE FROM ");
-        let from_table = query.get_from_table();
-        assert!(from_table.is_some(), "There should be table to delete from");
-        if from_table.is_some() {
-            w.append(&from_table.unwrap().complete_name());
-        }
-        if !query.filters.is_empty() {
-            w.left_river("WHERE ");
-            self.build_filters(&mut w, query, &query.filters);
-        }
-        w
+    let restore = if local {
+        builder.build_restore_local_statement_timeout(&previous)
+    } else {
+        builder.build_restore_statement_timeout(&previous)
+    };
+    let restored = restore.and_then(|restore| db.execute_sql(&restore.sql, &vec![]));
+    match (result, restored) {
+        (result, Ok(_)) => result,
+        (Ok(_), Err(e)) => Err(e),
+        // the error of the statement is kept, with the failed restore as its cause
+        (Err(e), Err(restore)) => Err(e.with_cause(restore.to_string())),
     }
-
-    fn sql_options(&self) -> Vec<SqlOption>;
-
 }
 
 
@@ -661,6 +627,19 @@ pub trait DatabaseDDL{
 
     /// set the primary key constraint of a table
     fn set_primary_constraint(&self, model: &Table);
+
+    /// create the table with the columns and records of the select query,
+    /// ie: `CREATE TABLE staging AS SELECT ..`
+    fn create_table_as(&self, name: &str, query: &Query) -> Result<(), DbError>;
+
+    /// create the index on the table
+    fn create_index(&self, table: &Table, index: &Index) -> Result<(), DbError>;
+
+    /// set the comment of the table
+    fn comment_on_table(&self, table: &Table, comment: &str) -> Result<(), DbError>;
+
+    /// set the comment of the column of the table
+    fn comment_on_column(&self, table: &Table, column: &Column, comment: &str) -> Result<(), DbError>;
 }
 
 
@@ -688,6 +667,10 @@ pub trait DatabaseDev{
     /// get the inherited columns of this table
     fn get_inherited_columns(&self, schema: &str, table: &str) -> Vec<String>;
 
+    /// get the enum types of this schema, with their values in the declared order
+    /// (enum_name, [values]), used in generating a rust enum for each of them
+    fn get_enum_types(&self, schema: &str) -> Vec<(String, Vec<String>)>;
+
     ///get the equivalent postgresql database data type to rust data type
     /// returns (module, type)
     fn dbtype_to_rust_type(&self, db_type: &str) -> (Vec<String>, String);
@@ -1,29 +1,216 @@
-use query::{Filter, Operand};
+use query::{Filter, Operand, Field, Function};
+use query::LOWER;
 use query::Query;
-use table::Table;
-use dao::Dao;
-use database::{Database, DbError};
+use table::{Table, Column};
+use dao::{Dao, Value};
+use database::{Database, DbError, SqlOption, Dialect};
 use table::IsTable;
 use dao::IsDao;
 use dao::ToValue;
 use query::Equality;
+use std::collections::BTreeMap;
+use std::cell::Cell;
+use chrono::offset::utc::UTC;
+
+/// the audit columns which are stamped when inserting and updating records,
+/// only the columns that exist on the table are set
+#[derive(Debug, Clone)]
+pub struct Audit {
+    pub created: String,
+    pub updated: String,
+    pub created_by: String,
+    pub updated_by: String,
+    /// the value set on the created_by and updated_by columns
+    pub current_user: Option<Value>,
+}
+
+impl Audit{
+
+    /// the audit columns of the record base: created, updated, created_by, updated_by
+    pub fn new(current_user: Option<Value>) -> Self {
+        Audit {
+            created: "created".to_string(),
+            updated: "updated".to_string(),
+            created_by: "created_by".to_string(),
+            updated_by: "updated_by".to_string(),
+            current_user: current_user,
+        }
+    }
+
+    fn stamp(&self, table: &Table, dao: &mut Dao, time_column: &str, user_column: &str) {
+        if table.has_column_name(time_column) {
+            dao.set_value(time_column, Value::DateTime(UTC::now()));
+        }
+        match self.current_user {
+            Some(ref user) if table.has_column_name(user_column) => {
+                dao.set_value(user_column, user.clone());
+            }
+            _ => (),
+        }
+    }
+
+    /// set the created and updated columns of a new record
+    pub fn stamp_insert(&self, table: &Table, dao: &mut Dao) {
+        self.stamp(table, dao, &self.created, &self.created_by);
+        self.stamp(table, dao, &self.updated, &self.updated_by);
+    }
+
+    /// set the updated columns of a modified record
+    pub fn stamp_update(&self, table: &Table, dao: &mut Dao) {
+        self.stamp(table, dao, &self.updated, &self.updated_by);
+    }
+}
+
+/// a referred record which is only retrieved from the database on the first access,
+/// the retrieved record is kept and returned on the succeeding accesses
+/// ie: `user_review.user.get(db)` retrieves the user of the review once
+#[derive(Debug, Clone)]
+pub struct Lazy<T> {
+    /// the values of the primary columns of the referred record, in their order
+    id: Vec<Value>,
+    resolved: Option<T>,
+}
+
+impl <T>Lazy<T> where T: IsTable + IsDao{
+
+    /// an unresolved reference to the record with these primary column values
+    pub fn new(id: Vec<Value>) -> Self {
+        Lazy {
+            id: id,
+            resolved: None,
+        }
+    }
+
+    /// the record is already available, no query will be issued
+    pub fn resolved(record: T) -> Self {
+        Lazy {
+            id: vec![],
+            resolved: Some(record),
+        }
+    }
+
+    /// whether the record has been retrieved
+    pub fn is_resolved(&self) -> bool {
+        self.resolved.is_some()
+    }
+
+    /// retrieve the record on the first call, then return the kept one
+    pub fn get(&mut self, db: &Database) -> Result<&T, DbError> {
+        if self.resolved.is_none() {
+            let table = T::table();
+            let primary = table.primary_columns();
+            assert!(primary.len() == self.id.len(),
+                    "The id should have a value for each primary column of {}",
+                    table.complete_name());
+            let mut q = Query::select_all();
+            q.from(&table);
+            for (column, value) in primary.iter().zip(self.id.iter()) {
+                q.add_filter(Filter::with_value(&column.name, Equality::EQ, value.clone()));
+            }
+            let record = try!(q.collect_one(db));
+            self.resolved = Some(record);
+        }
+        Ok(self.resolved.as_ref().unwrap())
+    }
+}
 
 /// A higher level API for manipulating objects in the database
 /// This serves as a helper function for the query api
 pub struct EntityManager<'a> {
     pub db: &'a Database,
+    /// tables which are soft deleted, mapped to their flag column
+    /// ie: bazaar.product => active
+    pub soft_delete_tables: BTreeMap<String, String>,
+    /// when set, the audit columns are stamped on insert and update
+    pub audit: Option<Audit>,
+    /// the most records retrieved when getting all the records of a table
+    pub max_page_size: Option<usize>,
+    /// whether the last records retrieved were capped to the max page size
+    truncated: Cell<bool>,
 }
 
 impl <'a>EntityManager<'a>{
 
     /// Create an entity manager with the database connection provided
     pub fn new(db: &'a Database) -> Self {
-        EntityManager { db: db }
+        EntityManager {
+            db: db,
+            soft_delete_tables: BTreeMap::new(),
+            audit: None,
+            max_page_size: None,
+            truncated: Cell::new(false),
+        }
+    }
+
+    /// cap the number of records retrieved when getting all the records of a table,
+    /// a safety net against accidentally retrieving a huge table.
+    /// The records over the max are left out, which is told by `is_truncated`,
+    /// the query api can page through all of them
+    pub fn set_max_page_size(&mut self, max_page_size: usize) -> &mut Self {
+        self.max_page_size = Some(max_page_size);
+        self
     }
 
-    /// delete records of this table
+    /// whether the records last retrieved were capped to the max page size
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.get()
+    }
+
+    /// collect the records, limited to the max page size when the query has no limit
+    fn collect_capped<T>(&self, query: &mut Query) -> Result<Vec<T>, DbError>
+        where T: IsTable + IsDao
+    {
+        self.truncated.set(false);
+        match self.max_page_size {
+            Some(max_page_size) if query.page_size.is_none() => {
+                // one more record tells whether there are more than the max
+                query.set_page_size(max_page_size + 1);
+                let mut records: Vec<T> = try!(query.collect(self.db));
+                if records.len() > max_page_size {
+                    records.truncate(max_page_size);
+                    self.truncated.set(true);
+                }
+                Ok(records)
+            }
+            _ => query.collect(self.db),
+        }
+    }
+
+    /// stamp the audit columns of the records when inserting and updating
+    pub fn set_audit(&mut self, audit: Audit) -> &mut Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// deleting records of this table will set the column to false instead,
+    /// and only the records where the column is true are retrieved
+    pub fn enable_soft_delete(&mut self, table: &Table, column: &str) -> &mut Self {
+        self.soft_delete_tables.insert(table.complete_name(), column.to_string());
+        self
+    }
+
+    /// mark the query to exclude the soft deleted records, when enabled on the table.
+    /// To also retrieve the soft deleted records, use `Query::with_inactive`
+    fn apply_soft_delete(&self, table: &Table, query: &mut Query) {
+        match self.soft_delete_tables.get(&table.complete_name()) {
+            Some(column) => {
+                query.soft_delete(column);
+            }
+            None => (),
+        }
+    }
+
+    /// delete records of this table,
+    /// the records are only flagged as inactive when soft delete is enabled on the table
     pub fn delete(&self, table: &Table, filters: Vec<Filter>) -> usize {
-        let mut query = Query::delete();
+        let mut query = match self.soft_delete_tables.get(&table.complete_name()) {
+            Some(column) => {
+                let mut update = Query::update();
+                update.set(column, &false);
+                update
+            }
+            None => Query::delete(),
+        };
         query.from(table);
         for filter in filters {
             query.add_filter(filter);
@@ -40,8 +227,9 @@ impl <'a>EntityManager<'a>{
     {
         let table = T::table();
         let mut q = Query::select_all();
-        q.from_table(&table.complete_name());
-        q.collect(self.db)
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
+        self.collect_capped(&mut q)
     }
 
     /// get all the records of this table, but return only the columns mentioned
@@ -50,9 +238,10 @@ impl <'a>EntityManager<'a>{
     {
         let table = T::table();
         let mut q = Query::select();
-        q.from_table(&table.complete_name());
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
         q.columns(columns);
-        q.collect(self.db)
+        self.collect_capped(&mut q)
     }
 
     /// get all the records of this table, ignoring the columns listed, mentioned the other else
@@ -61,12 +250,13 @@ impl <'a>EntityManager<'a>{
     {
         let table = T::table();
         let mut q = Query::select();
-        q.from_table(&table.complete_name());
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
         for c in table.columns {
             q.column(&c.name);
         }
         q.exclude_columns(ignore_columns);
-        q.collect(self.db)
+        self.collect_capped(&mut q)
     }
 
 
@@ -77,8 +267,9 @@ impl <'a>EntityManager<'a>{
         let table = T::table();
         let mut q = Query::select_all();
         q.distinct();
-        q.from_table(&table.complete_name());
-        q.collect(self.db)
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
+        self.collect_capped(&mut q)
     }
 
     /// get all the records on this table which passed thru the filters
@@ -88,11 +279,12 @@ impl <'a>EntityManager<'a>{
     {
         let table = T::table();
         let mut q = Query::select_all();
-        q.from_table(&table.complete_name());
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
         for f in filters {
             q.add_filter(f);
         }
-        q.collect(self.db)
+        self.collect_capped(&mut q)
     }
 
     /// get the first records of this table that passed thru the filters
@@ -101,7 +293,8 @@ impl <'a>EntityManager<'a>{
     {
         let table = T::table();
         let mut q = Query::select_all();
-        q.from_table(&table.complete_name());
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
         q.add_filter(filter);
         q.collect_one(self.db)
     }
@@ -117,61 +310,322 @@ impl <'a>EntityManager<'a>{
                 "There should only be 1 primary column for this to work");
         let pk = primary[0].name.to_string();
 
-        Query::select_all()
-            .from_table(&table.complete_name())
-            .filter(&pk, Equality::EQ, id)
-            .collect_one(self.db)
+        let mut q = Query::select_all();
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
+        q.filter(&pk, Equality::EQ, id)
+         .collect_one(self.db)
     }
 
-    pub fn insert<T>(&self, dao: Dao) -> Result<T, DbError>
+    /// get the records matching any of the ids in a single query,
+    /// each id has the values of the primary columns of the table, in their order
+    pub fn find_by_ids<T>(&self, ids: &[Vec<&ToValue>]) -> Result<Vec<T>, DbError>
         where T: IsTable + IsDao
     {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
         let table = T::table();
-        let mut q = Query::insert();
-        q.into_table(&table.complete_name());
-        for key in dao.values.keys() {
-            q.column(key);
+        let primary_columns = table.primary_columns();
+        let primary: Vec<&str> = primary_columns.iter().map(|c| &*c.name).collect();
+        assert!(!primary.is_empty(),
+                "There should be a primary column for this to work");
+        let mut q = Query::select_all();
+        q.from(&table);
+        self.apply_soft_delete(&table, &mut q);
+        q.filter_row_in(primary, ids.to_vec())
+         .collect(self.db)
+    }
+
+    /// check if there is a record where the column is equal to the value ignoring the case,
+    /// ie: `WHERE LOWER(username) = LOWER($1)`, useful before inserting a unique name
+    pub fn exists_ci<T>(&self, column: &str, value: &ToValue) -> Result<bool, DbError>
+        where T: IsTable
+    {
+        let table = T::table();
+        let lowered_value = Function::new("LOWER", vec![Operand::Value(value.to_db_type())]);
+        let mut q = Query::select();
+        q.field(Field {
+             operand: Operand::Raw("1".to_string()),
+             name: Some("found".to_string()),
+         })
+         .from(&table)
+         .add_filter(Filter::bare_new(Operand::Function(LOWER(column)),
+                                      Equality::EQ,
+                                      Operand::Function(lowered_value)))
+         .set_page_size(1);
+        self.apply_soft_delete(&table, &mut q);
+        let found = try!(q.retrieve_one(self.db));
+        Ok(found.is_some())
+    }
+
+    /// delete the records with these primary keys in a single statement,
+    /// each id has a value for each of the primary columns.
+    /// An empty list is refused, instead of deleting all the records.
+    /// The records are only flagged as inactive when soft delete is enabled on the table
+    pub fn delete_by_ids<T>(&self, ids: &[Vec<&ToValue>]) -> Result<usize, DbError>
+        where T: IsTable
+    {
+        if ids.is_empty() {
+            return Err(DbError::new("There should be ids of the records to delete"));
         }
-        q.return_all();
-        for c in &table.columns {
-            let value = dao.values.get(&c.name);
-            match value {
-                Some(value) => {
-                    q.add_value(Operand::Value(value.clone()));
-                }
-                None => (),
+        let table = T::table();
+        let primary_columns = table.primary_columns();
+        let primary: Vec<&str> = primary_columns.iter().map(|c| &*c.name).collect();
+        assert!(!primary.is_empty(),
+                "There should be a primary column for this to work");
+        let mut q = match self.soft_delete_tables.get(&table.complete_name()) {
+            Some(column) => {
+                let mut update = Query::update();
+                update.set(column, &false);
+                update
             }
-        }
-        q.collect_one(self.db)
+            None => Query::delete(),
+        };
+        q.from(&table);
+        q.filter_row_in(primary, ids.to_vec())
+         .execute(self.db)
+    }
+
+    /// insert the record, returns the inserted record
+    /// including the values generated by the database, such as the defaults
+    pub fn insert<T>(&self, dao: Dao) -> Result<T, DbError>
+        where T: IsTable + IsDao
+    {
+        self.insert_with_ignore_columns(dao, vec![])
     }
 
     /// insert this record on the database, ignoring some columns
     /// which are set by the database default
     /// columns that are ignored are set by the database automatically
     pub fn insert_with_ignore_columns<T>(&self,
-                                         dao: Dao,
+                                         mut dao: Dao,
                                          ignore_columns: Vec<&str>)
                                          -> Result<T, DbError>
         where T: IsTable + IsDao
     {
         let table = T::table();
+        match self.audit {
+            Some(ref audit) => audit.stamp_insert(&table, &mut dao),
+            None => (),
+        }
         let mut q = Query::insert();
-        q.into_table(&table.complete_name());
-        for key in dao.values.keys() {
-            q.column(key);
+        q.into_(&table);
+        // the columns and values are added in pairs to keep them in the same order
+        for c in &table.columns {
+            // generated columns can't be written
+            if ignore_columns.contains(&&*c.name) || c.is_generated {
+                continue;
+            }
+            match dao.values.get(&c.name) {
+                Some(value) => {
+                    q.column(&c.name);
+                    q.add_value(Operand::Value(value.clone()));
+                }
+                None => (),
+            }
         }
-        q.exclude_columns(ignore_columns);
-        q.return_all();
+        if self.db.sql_options().contains(&SqlOption::SupportsReturningClause) {
+            q.return_all();
+            q.collect_one(self.db)
+        } else {
+            let primary_columns = table.primary_columns();
+            let missing = primary_columns.iter()
+                                         .filter(|pk| {
+                                             match dao.values.get(&pk.name) {
+                                                 Some(&Value::Null) | None => true,
+                                                 Some(_) => false,
+                                             }
+                                         })
+                                         .count();
+            // checked before inserting, only a single generated key is told by the database
+            if missing > 1 ||
+               (missing == 1 &&
+                !self.db.sql_options().contains(&SqlOption::SupportsLastInsertId)) {
+                return Err(DbError::new("The primary key is needed to retrieve the inserted \
+                                         record"));
+            }
+            try!(q.execute(self.db));
+            // retrieve the inserted record by its primary key, to get the generated values
+            let mut select = Query::select_all();
+            select.from(&table);
+            for pk in primary_columns {
+                let value = match dao.values.get(&pk.name) {
+                    Some(&Value::Null) | None => try!(self.db.last_insert_id()),
+                    Some(value) => value.clone(),
+                };
+                select.add_filter(Filter::with_value(&pk.name, Equality::EQ, value));
+            }
+            select.collect_one(self.db)
+        }
+    }
+
+    /// insert the records with multi-row inserts of `chunk_size` records each,
+    /// all in one transaction, returns the number of inserted records.
+    /// Within a transaction already started, the caller commits or rolls back.
+    /// Without a chunk size, the chunks are as large as the cap on the parameters
+    /// of a statement allows. The values missing in a record are set to the column default,
+    /// except in sqlite which has no DEFAULT in the values, they are inserted as NULL.
+    /// The NULL values are inserted as is, but a NULL primary key is left to its default
+    /// as the key of a new record, like in `save`.
+    pub fn insert_all_chunked<T>(&self,
+                                 entities: &[T],
+                                 chunk_size: Option<usize>)
+                                 -> Result<usize, DbError>
+        where T: IsTable + IsDao
+    {
+        if entities.is_empty() {
+            return Ok(0);
+        }
+        let table = T::table();
+        let mut daos = vec![];
+        for entity in entities {
+            let mut dao = entity.to_dao();
+            match self.audit {
+                Some(ref audit) => audit.stamp_insert(&table, &mut dao),
+                None => (),
+            }
+            daos.push(dao);
+        }
+        // the value of the column in the record, None when it is left to the default
+        let value_of = |dao: &Dao, column: &Column| -> Option<Value> {
+            match dao.values.get(&column.name) {
+                Some(&Value::Null) if column.is_primary => None,
+                Some(value) => Some(value.clone()),
+                None => None,
+            }
+        };
+        let columns: Vec<&Column> = table.columns
+                                         .iter()
+                                         .filter(|c| !c.is_generated)
+                                         .filter(|c| {
+                                             daos.iter().any(|dao| value_of(dao, c).is_some())
+                                         })
+                                         .collect();
+        if columns.is_empty() {
+            return Err(DbError::new("There are no values to insert"));
+        }
+        let max_rows = max_params(&self.db.dialect()) / columns.len();
+        if max_rows == 0 {
+            return Err(DbError::from_string(format!("{} columns exceed the parameters of a \
+                                                     statement",
+                                                    columns.len())));
+        }
+        let chunk_size = match chunk_size {
+            Some(0) => return Err(DbError::new("The chunk size should be at least 1")),
+            Some(chunk_size) if chunk_size > max_rows => {
+                return Err(DbError::from_string(format!("{} rows of {} columns exceed the \
+                                                         parameters of a statement, use at \
+                                                         most {} rows",
+                                                        chunk_size,
+                                                        columns.len(),
+                                                        max_rows)));
+            }
+            Some(chunk_size) => chunk_size,
+            None => max_rows,
+        };
+        let missing = match self.db.dialect() {
+            Dialect::Sqlite => Operand::Value(Value::Null),
+            _ => Operand::Default,
+        };
+        let own_transaction = !self.db.in_transaction();
+        if own_transaction {
+            try!(self.db.try_begin());
+        }
+        let mut inserted = 0;
+        for chunk in daos.chunks(chunk_size) {
+            let mut q = Query::insert();
+            q.into_(&table);
+            for column in &columns {
+                q.column(&column.name);
+            }
+            for dao in chunk {
+                let row = columns.iter()
+                                 .map(|column| {
+                                     match value_of(dao, column) {
+                                         Some(value) => Operand::Value(value),
+                                         None => missing.clone(),
+                                     }
+                                 })
+                                 .collect();
+                q.add_value_row(row);
+            }
+            match q.execute(self.db) {
+                Ok(count) => inserted += count,
+                Err(e) => {
+                    if own_transaction {
+                        try!(self.db.try_rollback());
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        if own_transaction {
+            try!(self.db.try_commit());
+        }
+        Ok(inserted)
+    }
+
+    /// insert the record, or update the existing record with the same values
+    /// on the conflict columns, returns the record and whether it was inserted.
+    /// Only supported in postgresql
+    pub fn upsert<T>(&self, dao: Dao, conflict_columns: Vec<&str>) -> Result<(T, bool), DbError>
+        where T: IsTable + IsDao
+    {
+        let sql_options = self.db.sql_options();
+        if !sql_options.contains(&SqlOption::SupportsOnConflict) ||
+           !sql_options.contains(&SqlOption::SupportsXmax) {
+            return Err(DbError::new("Upsert is not supported in this database"));
+        }
+        let table = T::table();
+        let mut q = Query::insert();
+        q.into_(&table);
+        let mut update_columns = vec![];
         for c in &table.columns {
-            let value = dao.values.get(&c.name);
-            match value {
+            if c.is_generated {
+                continue;
+            }
+            match dao.values.get(&c.name) {
                 Some(value) => {
+                    q.column(&c.name);
                     q.add_value(Operand::Value(value.clone()));
+                    if !conflict_columns.contains(&&*c.name) {
+                        update_columns.push(&*c.name);
+                    }
                 }
                 None => (),
             }
         }
-        q.collect_one(self.db)
+        // with only conflict columns it is DO NOTHING, which returns no existing record
+        let is_do_nothing = update_columns.is_empty();
+        q.on_conflict_update(conflict_columns.clone(), update_columns)
+         .return_all()
+         .return_inserted();
+        match try!(q.retrieve_one(self.db)) {
+            Some(mut dao) => {
+                let inserted: bool = dao.remove("inserted");
+                Ok((T::from_dao(&dao), inserted))
+            }
+            None if is_do_nothing => {
+                let mut select = Query::select_all();
+                select.from(&table);
+                for column in &conflict_columns {
+                    match dao.values.get(*column) {
+                        Some(value) => {
+                            select.add_filter(Filter::with_value(column, Equality::EQ, value.clone()));
+                        }
+                        None => {
+                            return Err(DbError::from_string(format!("No value for the conflict \
+                                                                     column {}",
+                                                                    column)))
+                        }
+                    }
+                }
+                let existing: T = try!(select.collect_one(self.db));
+                Ok((existing, false))
+            }
+            None => Err(DbError::new("No record returned from the upsert")),
+        }
     }
 
     /// insert this record on the database, explicitly setting the defaults of the columns
@@ -194,10 +648,45 @@ impl <'a>EntityManager<'a>{
     }
 
     /// update the Dao, return the updated Dao
+    /// the record is matched by the values of its primary columns
     pub fn update<T>(&self, dao: &Dao) -> Result<T, DbError>
         where T: IsTable + IsDao
     {
-        panic!("not yet")
+        let table = T::table();
+        let mut dao = dao.clone();
+        match self.audit {
+            Some(ref audit) => audit.stamp_update(&table, &mut dao),
+            None => (),
+        }
+        let mut q = Query::update();
+        q.from(&table);
+        for (column, value) in &dao.values {
+            if !table.is_primary(column) && !table.is_generated(column) {
+                q.column(column);
+                q.add_value(Operand::Value(value.clone()));
+            }
+        }
+        let primary = table.primary_columns();
+        assert!(!primary.is_empty(),
+                "There should be a primary column for this to work");
+        let mut filters = vec![];
+        for pk in primary {
+            let value = dao.get_value(&pk.name);
+            filters.push(Filter::with_value(&pk.name, Equality::EQ, value));
+        }
+        q.add_filters(filters.clone());
+        // the returned record includes the values set by the database,
+        // such as the columns maintained by triggers
+        if self.db.sql_options().contains(&SqlOption::SupportsReturningClause) {
+            q.return_all();
+            q.collect_one(self.db)
+        } else {
+            try!(q.execute(self.db));
+            let mut select = Query::select_all();
+            select.from(&table);
+            select.add_filters(filters);
+            select.collect_one(self.db)
+        }
     }
 
     /// update the Dao, return the updated Dao
@@ -236,14 +725,87 @@ impl <'a>EntityManager<'a>{
     }
 
     /// whether to use insert or update
-    /// insert when it is a new record
-    /// update when it is an existing recor
-    /// may use UPSERT in newer versions of postgres
-    /// may use MERGE in oracle, mssql
+    /// insert when it is a new record, that is when its primary key is not set,
+    /// update when it is an existing record.
+    /// On insert, only the primary key generated by the database is returned and merged
+    /// into the record, the other columns are not read back.
+    /// Without RETURNING, a single generated key is told by the database after the insert.
+    /// The unset columns are left to their defaults
     pub fn save<T>(&self, dao: T) -> Result<T, DbError>
         where T: IsTable + IsDao
     {
-        panic!("not yet");
+        let table = T::table();
+        let mut dao = dao.to_dao();
+        let primary = table.primary_columns();
+        assert!(!primary.is_empty(),
+                "There should be a primary column for this to work");
+        let is_new = primary.iter().any(|pk| {
+            match dao.values.get(&pk.name) {
+                Some(&Value::Null) | None => true,
+                Some(_) => false,
+            }
+        });
+        if !is_new {
+            return self.update(&dao);
+        }
+        match self.audit {
+            Some(ref audit) => audit.stamp_insert(&table, &mut dao),
+            None => (),
+        }
+        let mut q = Query::insert();
+        q.into_(&table);
+        for c in &table.columns {
+            if c.is_generated {
+                continue;
+            }
+            match dao.values.get(&c.name) {
+                Some(&Value::Null) | None => (),
+                Some(value) => {
+                    q.column(&c.name);
+                    q.add_value(Operand::Value(value.clone()));
+                }
+            }
+        }
+        let primary_names: Vec<&str> = primary.iter().map(|pk| &*pk.name).collect();
+        let returned = if self.db.sql_options().contains(&SqlOption::SupportsReturningClause) {
+            q.returns(primary_names);
+            try!(q.retrieve_one(self.db))
+        } else {
+            let missing = primary.iter()
+                                 .filter(|pk| {
+                                     match dao.values.get(&pk.name) {
+                                         Some(&Value::Null) | None => true,
+                                         Some(_) => false,
+                                     }
+                                 })
+                                 .count();
+            if missing > 1 || !self.db.sql_options().contains(&SqlOption::SupportsLastInsertId) {
+                return Err(DbError::new("Unable to tell the generated primary key \
+                                         without a RETURNING clause"));
+            }
+            try!(q.execute(self.db));
+            // read back only the primary key of the inserted record
+            let mut select = Query::select();
+            select.columns(primary_names)
+                  .from(&table);
+            for pk in &primary {
+                let value = match dao.values.get(&pk.name) {
+                    Some(&Value::Null) | None => try!(self.db.last_insert_id()),
+                    Some(value) => value.clone(),
+                };
+                select.add_filter(Filter::with_value(&pk.name, Equality::EQ, value));
+            }
+            try!(select.retrieve_one(self.db))
+        };
+        match returned {
+            Some(returned) => {
+                for (column, value) in returned.values {
+                    dao.set_value(&column, value);
+                }
+                Ok(T::from_dao(&dao))
+            }
+            None => Err(DbError::new("No record returned from the insert")),
+        }
     }
     ///
     /// Search a set of record from the base Query that would have been returned by the base query
@@ -255,3 +817,11 @@ impl <'a>EntityManager<'a>{
     }
 
 }
+
+/// the cap on the number of parameters of a statement
+fn max_params(dialect: &Dialect) -> usize {
+    match *dialect {
+        Dialect::Postgres | Dialect::Mysql => 65535,
+        Dialect::Sqlite => 999,
+    }
+}
@@ -55,7 +55,8 @@
 //!             sub_table:vec![],
 //!             comment:None,
 //!             columns:vec![],
-//!             is_view: false
+//!             is_view: false,
+//!             foreign_keys: vec![],
 //!         }
 //!     }
 //! }
@@ -76,6 +77,7 @@
 //!
 //!
 
+#[macro_use]
 extern crate postgres;
 #[cfg(feature = "sqlite")]
 extern crate rusqlite;
@@ -98,5 +100,6 @@ pub mod database;
 pub mod platform;
 pub mod table;
 pub mod writer;
+pub mod builder;
 pub mod config;
 pub mod pool;
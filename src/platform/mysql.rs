@@ -4,45 +4,192 @@ use dao::Dao;
 use dao::Value;
 use database::Database;
 use writer::SqlFrag;
+use pool::ConnectionHook;
 use database::SqlOption;
 
 use mysql::value::Value as MyValue;
-use mysql::error::{MyResult, MyError};
-use mysql::conn::Stmt;
-use mysql::conn::pool::MyPool;
+use mysql::error::MyError;
+use mysql::conn::pool::{MyPool, PooledConn};
 
-use table::Table;
+use table::{Table, Column, Index};
 use database::DatabaseDDL;
 use database::DbError;
+use database::IsolationLevel;
+use database::Dialect;
+use std::cell::RefCell;
+use std::cell::Cell;
 
 pub struct Mysql {
     pool: Option<MyPool>,
+    /// the connection of this platform, taken from the pool on first use
+    conn: RefCell<Option<PooledConn>>,
+    /// the version of the server, the newer servers have more sql options
+    server_version: Cell<Option<(u32, u32, u32)>>,
+    /// whether autocommit is on for the connection
+    auto_commit: Cell<bool>,
+    /// whether a transaction is started with begin
+    in_transaction: Cell<bool>,
+    /// hooks run before the connection is returned to the pool
+    on_release: Vec<ConnectionHook>,
+}
+
+impl Drop for Mysql {
+    /// the connection goes back to the pool, where the next user expects
+    /// no open transaction and autocommit to be on
+    fn drop(&mut self) {
+        if self.conn.borrow().is_none() {
+            return;
+        }
+        if self.in_transaction.get() || !self.auto_commit.get() {
+            match self.execute_sql("ROLLBACK", &vec![]) {
+                Ok(_) => self.in_transaction.set(false),
+                Err(e) => println!("Unable to rollback the open transaction {}", e),
+            }
+        }
+        if !self.auto_commit.get() {
+            match self.execute_sql("SET autocommit = 1", &vec![]) {
+                Ok(_) => (),
+                Err(e) => println!("Unable to turn autocommit back on {}", e),
+            }
+        }
+        let db: &Database = self;
+        for hook in &self.on_release {
+            match hook(db) {
+                Ok(_) => (),
+                Err(e) => println!("Unable to run the release hook {}", e),
+            }
+        }
+    }
 }
 
 impl From<MyError> for DbError {
     fn from(err: MyError) -> Self {
-        DbError::from_string(format!("{:?}", err))
+        match err {
+            MyError::MySqlError(ref my_error) => {
+                DbError::with_code(format!("{:?}", err), &format!("{}", my_error.code))
+                    .with_constraint(constraint_from_message(&my_error.message))
+            }
+            _ => DbError::from_string(format!("{:?}", err)),
+        }
+    }
+}
+
+/// mysql only mentions the constraint in the error message
+/// ie: Duplicate entry 'a@b.c' for key 'uq_users_email'
+/// ie: .. CONSTRAINT `fk_review_user` FOREIGN KEY ..
+fn constraint_from_message(message: &str) -> Option<String> {
+    if let Some(start) = message.find("CONSTRAINT `") {
+        let rest = &message[start + "CONSTRAINT `".len()..];
+        return rest.find('`').map(|end| rest[..end].to_string());
+    }
+    if let Some(start) = message.rfind("for key '") {
+        let rest = &message[start + "for key '".len()..];
+        return rest.find('\'').map(|end| rest[..end].to_string());
+    }
+    None
+}
+
+/// ie: "5.7.22-log" is (5, 7, 22)
+fn parse_server_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut numbers = version.split(|c: char| !c.is_digit(10)).map(|n| n.parse::<u32>());
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => Some((major, minor, patch)),
+        _ => None,
     }
 }
 
 impl Mysql{
 
     pub fn new() -> Self {
-        Mysql { pool: None }
+        Mysql {
+            pool: None,
+            conn: RefCell::new(None),
+            server_version: Cell::new(None),
+            auto_commit: Cell::new(true),
+            in_transaction: Cell::new(false),
+            on_release: vec![],
+        }
     }
 
     pub fn with_pooled_connection(pool: MyPool) -> Self {
-        Mysql { pool: Some(pool) }
+        let mysql = Mysql {
+            pool: Some(pool),
+            conn: RefCell::new(None),
+            server_version: Cell::new(None),
+            auto_commit: Cell::new(true),
+            in_transaction: Cell::new(false),
+            on_release: vec![],
+        };
+        mysql.read_server_version();
+        mysql
+    }
+
+    /// hooks run when this is dropped, before the connection is returned to the pool
+    pub fn on_release(&mut self, hooks: Vec<ConnectionHook>) {
+        self.on_release = hooks;
+    }
+
+    /// the version of the server the sql is built for, ie: "8.0.14",
+    /// it is read from the server when the connection is created
+    pub fn set_server_version(&self, version: &str) {
+        self.server_version.set(parse_server_version(version));
+    }
+
+    /// read the version of the server once, when the connection is created.
+    /// Building the sql doesn't touch the database, without a version the features
+    /// common to all the versions are used
+    fn read_server_version(&self) {
+        match self.execute_sql_with_one_return("SELECT VERSION() AS version", &vec![]) {
+            Ok(Some(dao)) => {
+                match dao.get_value("version") {
+                    Value::String(version) => self.set_server_version(&version),
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
     }
 
     fn from_rust_type_tosql(types: &Vec<Value>) -> Vec<MyValue> {
         let mut params: Vec<MyValue> = vec![];
         for t in types {
             match t {
+                &Value::Bool(x) => params.push(MyValue::Int(if x { 1 } else { 0 })),
+                &Value::I8(x) => params.push(MyValue::Int(x as i64)),
+                &Value::I16(x) => params.push(MyValue::Int(x as i64)),
+                &Value::I32(x) => params.push(MyValue::Int(x as i64)),
+                &Value::I64(x) => params.push(MyValue::Int(x)),
+                &Value::U8(x) => params.push(MyValue::UInt(x as u64)),
+                &Value::U16(x) => params.push(MyValue::UInt(x as u64)),
+                &Value::U32(x) => params.push(MyValue::UInt(x as u64)),
+                &Value::U64(x) => params.push(MyValue::UInt(x)),
+                &Value::F32(x) => params.push(MyValue::Float(x as f64)),
+                &Value::F64(x) => params.push(MyValue::Float(x)),
                 &Value::String(ref x) => {
                     params.push(MyValue::Bytes(x.as_bytes().to_owned()));
                 }
-                _ => panic!("not yet here {:?}", t),
+                &Value::VecU8(ref x) => params.push(MyValue::Bytes(x.clone())),
+                &Value::Json(ref x) => params.push(MyValue::Bytes(x.to_string().into_bytes())),
+                // stored in a CHAR(36) column
+                &Value::Uuid(ref x) => params.push(MyValue::Bytes(x.to_string().into_bytes())),
+                // DATETIME has no time zone, the timestamps are stored in UTC
+                &Value::DateTime(ref x) => {
+                    params.push(MyValue::Bytes(x.naive_utc().to_string().into_bytes()));
+                }
+                &Value::DateTimeOffset(ref x) => {
+                    params.push(MyValue::Bytes(x.naive_utc().to_string().into_bytes()));
+                }
+                &Value::NaiveDate(ref x) => params.push(MyValue::Bytes(x.to_string().into_bytes())),
+                &Value::NaiveTime(ref x) => params.push(MyValue::Bytes(x.to_string().into_bytes())),
+                &Value::NaiveDateTime(ref x) => {
+                    params.push(MyValue::Bytes(x.to_string().into_bytes()));
+                }
+                &Value::Money(ref x) => {
+                    params.push(MyValue::Bytes(x.amount.to_string().into_bytes()));
+                }
+                &Value::Null => params.push(MyValue::NULL),
+                // mysql has no array parameters
+                &Value::Array(_) | &Value::Object(_) => panic!("not yet here {:?}", t),
             }
         }
         params
@@ -122,8 +269,23 @@ impl Mysql{
 
     }
 
-    fn get_prepared_statement<'a>(&'a self, sql: &'a str) -> MyResult<Stmt> {
-        self.pool.as_ref().unwrap().prepare(sql)
+    /// run on the connection of this platform, it is kept until this is dropped.
+    /// Each statement sent to the pool itself could end up on a different connection,
+    /// losing the transaction and the session settings of the previous statements.
+    /// This is also the way to reach the driver for the features not covered here,
+    /// the raw calls then run in the same transaction as the rest.
+    pub fn with_conn<F, T>(&self, f: F) -> Result<T, DbError>
+        where F: FnOnce(&mut PooledConn) -> Result<T, DbError>
+    {
+        let mut conn = self.conn.borrow_mut();
+        if conn.is_none() {
+            let pool = match self.pool {
+                Some(ref pool) => pool,
+                None => return Err(DbError::new("No connection for this database")),
+            };
+            *conn = Some(try!(pool.get_conn()));
+        }
+        f(conn.as_mut().unwrap())
     }
 }
 
@@ -137,15 +299,61 @@ impl Database for Mysql{
             Err(e) => panic!(format!("{:?}", e)),
         }
     }
-    fn begin(&self) {
+    fn try_begin(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("START TRANSACTION", &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
+    }
+    fn begin_read_only(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("START TRANSACTION READ ONLY", &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
+    }
+    /// the isolation level is set prior to starting the transaction,
+    /// it only applies to the next transaction of the same connection
+    fn begin_with_isolation(&self, level: IsolationLevel) -> Result<(), DbError> {
+        let sql = format!("SET TRANSACTION ISOLATION LEVEL {}", level.to_sql());
+        println!("SQL: \n{}", sql);
+        try!(self.with_conn(|conn| {
+            try!(conn.query(&sql));
+            try!(conn.query("START TRANSACTION"));
+            Ok(())
+        }));
+        self.in_transaction.set(true);
+        Ok(())
     }
-    fn commit(&self) {
+    fn try_commit(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("COMMIT", &vec![]));
+        self.in_transaction.set(false);
+        Ok(())
     }
-    fn rollback(&self) {
+    fn try_rollback(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("ROLLBACK", &vec![]));
+        self.in_transaction.set(false);
+        Ok(())
     }
     fn is_transacted(&self) -> bool {
         false
     }
+    /// with autocommit off, the statements are always in a transaction
+    fn in_transaction(&self) -> bool {
+        self.in_transaction.get() || !self.auto_commit.get()
+    }
+    /// mysql itself commits the pending data changes when autocommit is turned back on,
+    /// the setting stays on the connection of this platform
+    fn set_auto_commit(&self, auto_commit: bool) -> Result<(), DbError> {
+        let sql = if auto_commit {
+            "SET autocommit = 1"
+        } else {
+            "SET autocommit = 0"
+        };
+        try!(self.execute_sql(sql, &vec![]));
+        if auto_commit {
+            self.in_transaction.set(false);
+        }
+        self.auto_commit.set(auto_commit);
+        Ok(())
+    }
     fn is_closed(&self) -> bool {
         false
     }
@@ -162,9 +370,45 @@ impl Database for Mysql{
 
     /// return this list of options, supported features in the database
     fn sql_options(&self) -> Vec<SqlOption> {
-        vec![
+        let mut options = vec![
             SqlOption::UsesQuestionMark,//mysql uses question mark instead of the numbered params
-        ]
+            SqlOption::SupportsWithRollup,
+            SqlOption::SupportsModifyLimit,
+            SqlOption::SupportsModifyOrderBy,
+            SqlOption::UsesGroupConcatSeparator,
+            SqlOption::SupportsAnyAll,
+            SqlOption::UsesBacktickQuote,
+            SqlOption::UsesIntervalUnit,
+            SqlOption::UsesEmptyValues,
+            SqlOption::UsesMaxExecutionTime,
+            SqlOption::SupportsReplaceInto,
+            SqlOption::UsesInlineComment,
+            SqlOption::UsesNullSafeEqual,
+            SqlOption::SupportsLastInsertId,
+        ];
+        // FOR SHARE and NOWAIT came with mysql 8.0, LATERAL with 8.0.14
+        match self.server_version.get() {
+            Some(version) if version >= (8, 0, 0) => {
+                options.push(SqlOption::SupportsRowLock);
+                if version >= (8, 0, 14) {
+                    options.push(SqlOption::SupportsLateral);
+                }
+            }
+            _ => (),
+        }
+        options
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Mysql
+    }
+
+    fn last_insert_id(&self) -> Result<Value, DbError> {
+        let sql = "SELECT LAST_INSERT_ID() AS id";
+        match try!(self.execute_sql_with_one_return(sql, &vec![])) {
+            Some(dao) => Ok(dao.get_value("id")),
+            None => Err(DbError::new("No last insert id returned")),
+        }
     }
 
     fn update(&self, query: &Query) -> Dao {
@@ -177,28 +421,29 @@ impl Database for Mysql{
     fn execute_sql_with_return(&self, sql: &str, params: &Vec<Value>) -> Result<Vec<Dao>, DbError> {
         println!("SQL: \n{}", sql);
         println!("param: {:?}", params);
-        assert!(self.pool.is_some());
-        let mut stmt = try!(self.get_prepared_statement(sql));
-        let mut columns = vec![];
-        for col in stmt.columns_ref().unwrap() {
-            let column_name = String::from_utf8(col.name.clone()).unwrap();
-            columns.push(column_name);
-        }
-        let mut daos = vec![];
         let param = Mysql::from_rust_type_tosql(params);
-        let rows = try!(stmt.execute(&param));
-        for row in rows {
-            let row = try!(row);
-            let mut index = 0;
-            let mut dao = Dao::new();
-            for col in &columns {
-                let rtype = Mysql::from_sql_to_rust_type(&row, index);
-                dao.set_value(col, rtype);
-                index += 1;
-            }
-            daos.push(dao);
-        }
-        Ok(daos)
+        self.with_conn(|conn| {
+            let mut stmt = try!(conn.prepare(sql));
+            let mut columns = vec![];
+            for col in stmt.columns_ref().unwrap() {
+                let column_name = String::from_utf8(col.name.clone()).unwrap();
+                columns.push(column_name);
+            }
+            let mut daos = vec![];
+            let rows = try!(stmt.execute(&param));
+            for row in rows {
+                let row = try!(row);
+                let mut index = 0;
+                let mut dao = Dao::new();
+                for col in &columns {
+                    let rtype = Mysql::from_sql_to_rust_type(&row, index);
+                    dao.set_value(col, rtype);
+                    index += 1;
+                }
+                daos.push(dao);
+            }
+            Ok(daos)
+        })
     }
 
     fn execute_sql_with_one_return(&self,
@@ -220,9 +465,25 @@ impl Database for Mysql{
         println!("SQL: \n{}", sql);
         println!("param: {:?}", params);
         let to_sql_types = Mysql::from_rust_type_tosql(params);
-        assert!(self.pool.is_some());
-        let result = try!(self.pool.as_ref().unwrap().prep_exec(sql, &to_sql_types));
-        Ok(result.affected_rows() as usize)
+        self.with_conn(|conn| {
+            let result = try!(conn.prep_exec(sql, &to_sql_types));
+            Ok(result.affected_rows() as usize)
+        })
+    }
+
+    /// the text protocol is used here since prepared statements can only have one statement
+    fn execute_batch(&self, sql: &str) -> Result<(), DbError> {
+        println!("SQL: \n{}", sql);
+        self.with_conn(|conn| {
+            let mut result = try!(conn.query(sql));
+            // the error of a later statement only shows up when its result set is reached
+            while result.more_results_exists() {
+                for row in result.by_ref() {
+                    try!(row);
+                }
+            }
+            Ok(())
+        })
     }
 
 }
@@ -249,10 +510,17 @@ impl DatabaseDDL for Mysql{
             } else {
                 do_comma = true;
             }
-            w.append(&c.name);
+            w.column_name(&c.name);
             w.append(" ");
             let dt = self.rust_type_to_dbtype(&c.data_type);
             w.append(&dt);
+            match c.collation {
+                Some(ref collation) => self.sql_builder().build_collation(&mut w, collation),
+                None => (),
+            }
+            if c.is_identity {
+                w.append(" AUTO_INCREMENT");
+            }
             if c.is_primary {
                 w.append(" PRIMARY KEY ");
             }
@@ -277,14 +545,56 @@ impl DatabaseDDL for Mysql{
     }
 
     fn set_foreign_constraint(&self, model: &Table) {
-        panic!("not yet");
+        for foreign_key in &model.foreign_keys {
+            let frag = self.sql_builder().build_add_foreign_key(model, foreign_key);
+            match self.execute_sql(&frag.sql, &vec![]) {
+                Ok(_) => println!("added foreign key.."),
+                Err(e) => panic!("foreign key not added {}", e),
+            }
+        }
     }
 
     fn set_primary_constraint(&self, model: &Table) {
         panic!("not yet");
     }
+
+    fn create_table_as(&self, name: &str, query: &Query) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_create_table_as(name, query));
+        try!(self.execute_sql(&frag.sql, &frag.params));
+        Ok(())
+    }
+
+    fn create_index(&self, table: &Table, index: &Index) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_create_index(table, index));
+        try!(self.execute_sql(&frag.sql, &frag.params));
+        Ok(())
+    }
+
+    fn comment_on_table(&self, table: &Table, comment: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_comment_on_table(table, comment));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
+    fn comment_on_column(&self, table: &Table, column: &Column, comment: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_comment_on_column(table, column, comment));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
 }
 
 
 // TODO: need to implement trait DatabaseDev for Mysql
 // Mysql can be used as development database
+
+#[test]
+fn test_constraint_from_message() {
+    assert_eq!(constraint_from_message("Duplicate entry 'a@b.c' for key 'uq_users_email'"),
+               Some("uq_users_email".to_string()));
+    assert_eq!(constraint_from_message("Cannot add or update a child row: a foreign key \
+                                        constraint fails (`bazaar`.`review`, CONSTRAINT \
+                                        `fk_review_user` FOREIGN KEY (`user_id`) REFERENCES \
+                                        `users` (`user_id`))"),
+               Some("fk_review_user".to_string()));
+    assert_eq!(constraint_from_message("Lock wait timeout exceeded"), None);
+}
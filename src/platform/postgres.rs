@@ -1,28 +1,291 @@
 use query::Query;
-use table::{Table, Column, Foreign};
+use table::{Table, Column, Foreign, Index};
 use dao::Dao;
 
 use postgres::Connection;
 use regex::Regex;
 use dao::Value;
-use database::{Database, DatabaseDev, DatabaseDDL, DbError};
+use dao::Array;
+use dao::Money;
+use database::{Database, DatabaseDev, DatabaseDDL, DbError, IsolationLevel, Dialect};
 use postgres::error::Error as PgError;
 use postgres::types::Type;
 use postgres::types::ToSql;
+use postgres::types::FromSql;
+use postgres::types::IsNull;
+use postgres::types::SessionInfo;
+use postgres::Result as PgResult;
 use writer::SqlFrag;
+use pool::ConnectionHook;
 use postgres::rows::Row;
+use postgres::stmt::Statement;
 use database::SqlOption;
 use r2d2::PooledConnection;
 use r2d2_postgres::PostgresConnectionManager;
+use std::cell::Cell;
+use rustc_serialize::json::Json;
+use chrono::datetime::DateTime;
+use chrono::offset::utc::UTC;
+use chrono::offset::fixed::FixedOffset;
+use std::io::Read;
+use std::io::Write;
 
 pub struct Postgres {
     /// a connection pool is provided
     pub pool: Option<PooledConnection<PostgresConnectionManager>>,
+    /// the number of rows retrieved from the server at a time,
+    /// all the rows are retrieved at once when None
+    fetch_size: Option<i32>,
+    /// whether a transaction is started with begin
+    in_transaction: Cell<bool>,
+    /// when off, a new transaction is started when the previous one ends
+    auto_commit: Cell<bool>,
+    /// hooks run before the connection is returned to the pool
+    on_release: Vec<ConnectionHook>,
+    /// the offset the timestamps with time zone are converted to when read
+    time_zone: Option<FixedOffset>,
+    /// the currency of the money columns, they are read as the minor units when None
+    money_currency: Option<String>,
+    /// read the numeric columns as their exact decimal text instead of a f64
+    exact_numeric: bool,
 }
 
 impl From<PgError> for DbError {
     fn from(err: PgError) -> Self {
-        DbError::from_string(format!("{:?}", err))
+        match err {
+            PgError::Db(ref db_error) => {
+                DbError::with_code(format!("{:?}", err), db_error.code.code())
+                    .with_constraint(db_error.constraint.clone())
+            }
+            _ => DbError::from_string(format!("{:?}", err)),
+        }
+    }
+}
+
+impl Drop for Postgres {
+    /// the connection goes back to the pool, where the next user expects no open transaction
+    fn drop(&mut self) {
+        if self.pool.is_some() {
+            if self.in_transaction.get() {
+                match self.execute_sql("ROLLBACK", &vec![]) {
+                    Ok(_) => self.in_transaction.set(false),
+                    Err(e) => println!("Unable to rollback the open transaction {}", e),
+                }
+            }
+            let db: &Database = self;
+            for hook in &self.on_release {
+                match hook(db) {
+                    Ok(_) => (),
+                    Err(e) => println!("Unable to run the release hook {}", e),
+                }
+            }
+        }
+    }
+}
+
+/// the text of the types which are sent as their text,
+/// ie: the label of an enum, a json document
+struct RawText(String);
+
+impl FromSql for RawText{
+    fn from_sql<R: Read>(ty: &Type, raw: &mut R, _: &SessionInfo) -> PgResult<Self> {
+        // jsonb starts with its version
+        if *ty == Type::Jsonb {
+            let mut version = [0u8; 1];
+            try!(raw.read_exact(&mut version).map_err(PgError::Io));
+        }
+        let mut text = String::new();
+        try!(raw.read_to_string(&mut text).map_err(PgError::Io));
+        Ok(RawText(text))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::Json | Type::Jsonb | Type::Other(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// the exact decimal text of a numeric, which has more digits than a f64 holds
+struct NumericText(String);
+
+impl FromSql for NumericText{
+    fn from_sql<R: Read>(_: &Type, raw: &mut R, _: &SessionInfo) -> PgResult<Self> {
+        let mut bytes = vec![];
+        try!(raw.read_to_end(&mut bytes).map_err(PgError::Io));
+        // ndigits, weight, sign, dscale, then the digits in base 10000
+        let words: Vec<i16> = bytes.chunks(2)
+                                   .map(|pair| ((pair[0] as u16) << 8 | pair[1] as u16) as i16)
+                                   .collect();
+        if words.len() < 4 {
+            return Err(PgError::Conversion("Invalid numeric".into()));
+        }
+        let weight = words[1] as i32;
+        let sign = words[2] as u16;
+        let dscale = words[3] as usize;
+        let digits = &words[4..];
+        if sign == 0xC000 {
+            return Ok(NumericText("NaN".to_string()));
+        }
+        let digit = |index: i32| {
+            if index >= 0 && (index as usize) < digits.len() {
+                digits[index as usize]
+            } else {
+                0
+            }
+        };
+        let mut text = String::new();
+        if sign == 0x4000 {
+            text.push('-');
+        }
+        if weight < 0 {
+            text.push('0');
+        } else {
+            text.push_str(&format!("{}", digit(0)));
+            for index in 1..weight + 1 {
+                text.push_str(&format!("{:04}", digit(index)));
+            }
+        }
+        if dscale > 0 {
+            let mut fraction = String::new();
+            let mut index = weight + 1;
+            while fraction.len() < dscale {
+                fraction.push_str(&format!("{:04}", digit(index)));
+                index += 1;
+            }
+            fraction.truncate(dscale);
+            text.push('.');
+            text.push_str(&fraction);
+        }
+        Ok(NumericText(text))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::Numeric
+    }
+}
+
+/// the money is sent as an int8 of its minor units
+struct MinorUnits(i64);
+
+impl FromSql for MinorUnits{
+    fn from_sql<R: Read>(_: &Type, raw: &mut R, _: &SessionInfo) -> PgResult<Self> {
+        let mut bytes = [0u8; 8];
+        try!(raw.read_exact(&mut bytes).map_err(PgError::Io));
+        let amount = bytes.iter().fold(0i64, |acc, b| (acc << 8) | *b as i64);
+        Ok(MinorUnits(amount))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::Money
+    }
+}
+
+/// bound as its minor units to a money or a bigint column
+impl ToSql for Money{
+    fn to_sql<W: Write + ?Sized>(&self, _: &Type, out: &mut W, _: &SessionInfo) -> PgResult<IsNull> {
+        let mut bytes = [0u8; 8];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (self.amount >> (56 - 8 * i)) as u8;
+        }
+        try!(out.write_all(&bytes).map_err(PgError::Io));
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::Money | Type::Int8 => true,
+            _ => false,
+        }
+    }
+
+    to_sql_checked!();
+}
+
+/// a statement prepared on the server with `Postgres::prepare`,
+/// it is deallocated when this is dropped
+pub struct PreparedQuery<'a> {
+    postgres: &'a Postgres,
+    stmt: Statement<'a>,
+    pub sql: String,
+}
+
+impl<'a> PreparedQuery<'a>{
+
+    /// the number of `$n` parameters of the statement
+    pub fn param_count(&self) -> usize {
+        self.stmt.param_types().len()
+    }
+
+    /// execute the statement with these values of its parameters, returns the rows
+    pub fn execute(&self, params: &Vec<Value>) -> Result<Vec<Dao>, DbError> {
+        if params.len() != self.param_count() {
+            return Err(DbError::from_string(format!("The statement expects {} parameters, \
+                                                     {} given",
+                                                    self.param_count(),
+                                                    params.len())));
+        }
+        let param = self.postgres.from_rust_type_tosql(params);
+        let rows = try!(self.stmt.query(&param));
+        let mut daos = vec![];
+        for row in rows {
+            daos.push(self.postgres.row_to_dao(&row));
+        }
+        Ok(daos)
+    }
+}
+
+/// a node of the query plan from `EXPLAIN (FORMAT JSON)`,
+/// the costs and rows are the estimates of the planner
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PlanNode {
+    /// ie: `Seq Scan`, `Index Scan`, `Hash Join`
+    pub node_type: String,
+    /// the table scanned, if this is a scan node
+    pub relation_name: Option<String>,
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    pub plan_rows: f64,
+    /// the child nodes
+    pub plans: Vec<PlanNode>,
+}
+
+impl PlanNode{
+
+    /// read the node from the `Plan` object of the explain output
+    pub fn from_json(json: &Json) -> Result<Self, DbError> {
+        let node_type = match json.find("Node Type").and_then(|n| n.as_string()) {
+            Some(node_type) => node_type.to_string(),
+            None => return Err(DbError::new("The plan node has no Node Type")),
+        };
+        let number = |key: &str| json.find(key).and_then(|n| n.as_f64()).unwrap_or(0.0);
+        let mut plans = vec![];
+        match json.find("Plans").and_then(|p| p.as_array()) {
+            Some(children) => {
+                for child in children {
+                    plans.push(try!(PlanNode::from_json(child)));
+                }
+            }
+            None => (),
+        }
+        Ok(PlanNode {
+            node_type: node_type,
+            relation_name: json.find("Relation Name")
+                               .and_then(|r| r.as_string())
+                               .map(|r| r.to_string()),
+            startup_cost: number("Startup Cost"),
+            total_cost: number("Total Cost"),
+            plan_rows: number("Plan Rows"),
+            plans: plans,
+        })
+    }
+
+    /// whether this node or any of its child is of this node type,
+    /// ie: `plan.has_node("Seq Scan")`
+    pub fn has_node(&self, node_type: &str) -> bool {
+        self.node_type == node_type || self.plans.iter().any(|p| p.has_node(node_type))
     }
 }
 
@@ -36,12 +299,126 @@ impl Postgres{
     /// useful when just building sql queries specific to this platform
     /// inexpensive operation, so can have multiple instances
     pub fn new() -> Self {
-        Postgres { pool: None }
+        Postgres {
+            pool: None,
+            fetch_size: None,
+            in_transaction: Cell::new(false),
+            auto_commit: Cell::new(true),
+            on_release: vec![],
+            time_zone: None,
+            money_currency: None,
+            exact_numeric: false,
+        }
     }
 
 
     pub fn with_pooled_connection(pool: PooledConnection<PostgresConnectionManager>) -> Self {
-        Postgres { pool: Some(pool) }
+        Postgres {
+            pool: Some(pool),
+            fetch_size: None,
+            in_transaction: Cell::new(false),
+            auto_commit: Cell::new(true),
+            on_release: vec![],
+            time_zone: None,
+            money_currency: None,
+            exact_numeric: false,
+        }
+    }
+
+    /// hooks run when this is dropped, before the connection is returned to the pool,
+    /// ie: `RESET ALL`
+    pub fn on_release(&mut self, hooks: Vec<ConnectionHook>) {
+        self.on_release = hooks;
+    }
+
+    /// read the timestamps with time zone as `Value::DateTimeOffset` in this offset,
+    /// ie: the fixed time zone of the business, instead of UTC
+    pub fn set_time_zone(&mut self, offset: FixedOffset) {
+        self.time_zone = Some(offset);
+    }
+
+    /// read the money columns as `Value::Money` in this currency, ie: `USD`,
+    /// the money type itself only has the decimal places of the `lc_monetary` setting
+    pub fn set_money_currency(&mut self, currency: &str) {
+        self.money_currency = Some(currency.to_string());
+    }
+
+    /// read the numeric columns as `Value::String` of their exact decimal text,
+    /// so `Dao::try_get` fails instead of rounding the digits past the precision of a f64
+    pub fn set_exact_numeric(&mut self) {
+        self.exact_numeric = true;
+    }
+
+    /// retrieve the rows of the selects in batches of this size, to lower the peak memory.
+    /// The rows are fetched from a portal which only lives in a transaction,
+    /// so each select is wrapped in a transaction of its own.
+    /// Within a transaction started with begin, the rows are fetched from a cursor instead
+    pub fn set_fetch_size(&mut self, fetch_size: usize) {
+        assert!(fetch_size > 0, "The fetch size should be greater than 0");
+        self.fetch_size = Some(fetch_size as i32);
+    }
+
+    /// with auto commit off, the next transaction is started as soon as the previous one ends
+    fn continue_transaction(&self) -> Result<(), DbError> {
+        if !self.auto_commit.get() {
+            try!(self.try_begin());
+        }
+        Ok(())
+    }
+
+    /// the query plan of the query as estimated by the planner, the query is not executed
+    pub fn explain_json(&self, query: &mut Query) -> Result<PlanNode, DbError> {
+        let sql_frag = try!(query.build(self));
+        let sql = format!("EXPLAIN (FORMAT JSON) {}", sql_frag.sql);
+        let dao = match try!(self.execute_sql_with_one_return(&sql, &sql_frag.params)) {
+            Some(dao) => dao,
+            None => return Err(DbError::new("No query plan returned")),
+        };
+        let json = match dao.get_value("QUERY PLAN") {
+            Value::Json(json) => json,
+            Value::String(text) => {
+                match Json::from_str(&text) {
+                    Ok(json) => json,
+                    Err(e) => return Err(DbError::from_string(format!("{:?}", e))),
+                }
+            }
+            value => {
+                return Err(DbError::from_string(format!("Unexpected query plan {:?}", value)))
+            }
+        };
+        // the output is an array with one object for the statement
+        let plan = json.as_array()
+                       .and_then(|statements| statements.first())
+                       .and_then(|statement| statement.find("Plan"));
+        match plan {
+            Some(plan) => PlanNode::from_json(plan),
+            None => Err(DbError::new("No Plan in the explain output")),
+        }
+    }
+
+    /// prepare the statement of the query on the server once, to be executed many times.
+    /// The values in the query only mark the parameters, the actual values are given on execute
+    pub fn prepare<'a>(&'a self, query: &mut Query) -> Result<PreparedQuery<'a>, DbError> {
+        let sql_frag = try!(query.build(self));
+        let conn = self.get_connection();
+        let stmt = try!(conn.prepare(&sql_frag.sql));
+        Ok(PreparedQuery {
+            postgres: self,
+            stmt: stmt,
+            sql: sql_frag.sql,
+        })
+    }
+
+    /// convert the row into a dao, using the column names of the row
+    fn row_to_dao(&self, row: &Row) -> Dao {
+        let mut dao = Dao::new();
+        let mut index = 0;
+        for c in row.columns() {
+            let rtype = self.from_sql_to_rust_type(c.type_(), row, index);
+            dao.set_value(c.name(), rtype);
+            index += 1;
+        }
+        dao
     }
 
 
@@ -78,9 +455,18 @@ impl Postgres{
                 Value::VecU8(ref x) => params.push(x),
                 Value::Uuid(ref x) => params.push(x),
                 Value::DateTime(ref x) => params.push(x),
+                Value::DateTimeOffset(ref x) => params.push(x),
                 Value::NaiveDate(ref x) => params.push(x),
                 Value::NaiveTime(ref x) => params.push(x),
                 Value::NaiveDateTime(ref x) => params.push(x),
+                Value::Array(Array::Bool(ref x)) => params.push(x),
+                Value::Array(Array::I16(ref x)) => params.push(x),
+                Value::Array(Array::I32(ref x)) => params.push(x),
+                Value::Array(Array::I64(ref x)) => params.push(x),
+                Value::Array(Array::F64(ref x)) => params.push(x),
+                Value::Array(Array::String(ref x)) => params.push(x),
+                Value::Array(Array::Uuid(ref x)) => params.push(x),
+                Value::Money(ref x) => params.push(x),
                 Value::Null => panic!("unsupported/unexpected type! {:?}", t),
                 _ => panic!("not yet here {:?}", t),
             }
@@ -105,10 +491,19 @@ impl Postgres{
                     Err(_) => Value::Null,
                 }
             }
-            Type::TimestampTZ | Type::Timestamp => {
+            Type::TimestampTZ => {
+                let value: Result<DateTime<UTC>, _> = row.get_opt(index);
+                match (value, self.time_zone) {
+                    (Ok(value), Some(offset)) => Value::DateTimeOffset(value.with_timezone(&offset)),
+                    (Ok(value), None) => Value::DateTime(value),
+                    (Err(_), _) => Value::Null,
+                }
+            }
+            // timestamp without time zone can't be read as DateTime<UTC>
+            Type::Timestamp => {
                 let value = row.get_opt(index);
                 match value {
-                    Ok(value) => Value::DateTime(value),
+                    Ok(value) => Value::NaiveDateTime(value),
                     Err(_) => Value::Null,
                 }
             }
@@ -119,13 +514,35 @@ impl Postgres{
                     Err(_) => Value::Null,
                 }
             }
-            Type::Numeric | Type::Float8 => {
+            // the digits past the precision of a f64 are rounded, unless read exactly
+            Type::Numeric => {
+                let value: Result<NumericText, _> = row.get_opt(index);
+                match (value, self.exact_numeric) {
+                    (Ok(value), true) => Value::String(value.0),
+                    (Ok(value), false) => {
+                        match value.0.parse() {
+                            Ok(value) => Value::F64(value),
+                            Err(_) => Value::String(value.0),
+                        }
+                    }
+                    (Err(_), _) => Value::Null,
+                }
+            }
+            Type::Float8 => {
                 let value = row.get_opt(index);
                 match value {
                     Ok(value) => Value::F64(value),
                     Err(_) => Value::Null,
                 }
             }
+            Type::Money => {
+                let value: Result<MinorUnits, _> = row.get_opt(index);
+                match (value, self.money_currency.as_ref()) {
+                    (Ok(value), Some(currency)) => Value::Money(Money::new(value.0, currency)),
+                    (Ok(value), None) => Value::I64(value.0),
+                    (Err(_), _) => Value::Null,
+                }
+            }
             Type::Bool => {
                 let value = row.get_opt(index);
                 match value {
@@ -133,10 +550,10 @@ impl Postgres{
                     Err(_) => Value::Null,
                 }
             }
-            Type::Json => {
-                let value = row.get_opt(index);
+            Type::Json | Type::Jsonb => {
+                let value: Result<RawText, _> = row.get_opt(index);
                 match value {
-                    Ok(value) => Value::String(value),
+                    Ok(value) => Value::String(value.0),
                     Err(_) => Value::Null,
                 }
             }
@@ -196,6 +613,14 @@ impl Postgres{
                     Err(_) => Value::Null,
                 }
             }
+            // user defined types, read as text where possible, ie: enums
+            Type::Other(_) => {
+                let value: Result<RawText, _> = row.get_opt(index);
+                match value {
+                    Ok(value) => Value::String(value.0),
+                    Err(_) => Value::Null,
+                }
+            }
             _ => panic!("Type {:?} is not covered!", dtype),
         }
     }
@@ -231,6 +656,14 @@ impl Postgres{
                 WHERE pg_attribute.attrelid = pg_constraint.confrelid
                 AND pg_attribute.attnum = pg_constraint.confkey[1]
                 AND pg_attribute.attisdropped = false) AS foreign_column
+                ,(SELECT columns.is_identity = 'YES' FROM information_schema.columns
+                WHERE columns.table_schema = pg_namespace.nspname
+                AND columns.table_name = pg_class.relname
+                AND columns.column_name = pg_attribute.attname) AS is_identity
+                ,(SELECT columns.is_generated = 'ALWAYS' FROM information_schema.columns
+                WHERE columns.table_schema = pg_namespace.nspname
+                AND columns.table_name = pg_class.relname
+                AND columns.column_name = pg_attribute.attname) AS is_generated
                 ,pg_constraint.conname
 
             FROM pg_attribute
@@ -290,6 +723,14 @@ impl Postgres{
                 Ok(x) => Some(x),
                 Err(_) => None,
             };
+            let is_identity: bool = match row.get_opt("is_identity") {
+                Ok(x) => x,
+                Err(_) => false,
+            };
+            let is_generated: bool = match row.get_opt("is_generated") {
+                Ok(x) => x,
+                Err(_) => false,
+            };
 
             let foreign_schema: Option<String> = match row.get_opt("foreign_schema") {
                 Ok(x) => Some(x),
@@ -328,6 +769,9 @@ impl Postgres{
                 not_null: not_null,
                 foreign: foreign,
                 is_inherited: false, /* will be corrected later in the get_meta_data */
+                collation: None,
+                is_identity: is_identity,
+                is_generated: is_generated,
             };
             columns.push(column);
         }
@@ -406,15 +850,54 @@ impl Database for Postgres{
             Err(e) => panic!(format!("{:?}", e)),
         }
     }
-    fn begin(&self) {
+    fn try_begin(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("BEGIN", &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
+    }
+    fn begin_read_only(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("BEGIN TRANSACTION READ ONLY", &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
     }
-    fn commit(&self) {
+    fn begin_with_isolation(&self, level: IsolationLevel) -> Result<(), DbError> {
+        let sql = format!("BEGIN TRANSACTION ISOLATION LEVEL {}", level.to_sql());
+        try!(self.execute_sql(&sql, &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
     }
-    fn rollback(&self) {
+    fn try_commit(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("COMMIT", &vec![]));
+        self.in_transaction.set(false);
+        self.continue_transaction()
+    }
+    fn try_rollback(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("ROLLBACK", &vec![]));
+        self.in_transaction.set(false);
+        self.continue_transaction()
     }
     fn is_transacted(&self) -> bool {
         false
     }
+    fn in_transaction(&self) -> bool {
+        self.in_transaction.get()
+    }
+    /// postgresql has no auto commit setting, a transaction is started with BEGIN instead
+    fn set_auto_commit(&self, auto_commit: bool) -> Result<(), DbError> {
+        if auto_commit == self.auto_commit.get() {
+            return Ok(());
+        }
+        if auto_commit && self.in_transaction.get() {
+            try!(self.execute_sql("COMMIT", &vec![]));
+            self.in_transaction.set(false);
+        }
+        if !auto_commit && !self.in_transaction.get() {
+            try!(self.execute_sql("BEGIN", &vec![]));
+            self.in_transaction.set(true);
+        }
+        self.auto_commit.set(auto_commit);
+        Ok(())
+    }
     fn is_closed(&self) -> bool {
         false
     }
@@ -445,9 +928,28 @@ impl Database for Postgres{
             SqlOption::SupportsInheritance,
             SqlOption::UsesSchema,
             SqlOption::ReturnMetaColumns,// whether to use the column names returned in a statement
+            SqlOption::SupportsGroupingSets,
+            SqlOption::SupportsAnyAll,
+            SqlOption::SupportsDeferrable,
+            SqlOption::SupportsUpdateFrom,
+            SqlOption::SupportsPartialIndex,
+            SqlOption::SupportsStatementTimeout,
+            SqlOption::SupportsCommentOn,
+            SqlOption::SupportsLateral,
+            SqlOption::SupportsRowLock,
+            SqlOption::SupportsKeyLock,
+            SqlOption::SupportsOnConflict,
+            SqlOption::SupportsXmax,
+            SqlOption::SupportsArrayParam,
+            SqlOption::SupportsAggregateFilter,
+            SqlOption::SupportsTableSample,
         ]
     }
 
+    fn dialect(&self) -> Dialect {
+        Dialect::Postgres
+    }
+
 
     fn update(&self, query: &Query) -> Dao {
         panic!("not yet")
@@ -460,22 +962,47 @@ impl Database for Postgres{
         println!("SQL: \n{}", sql);
         println!("param: {:?}", params);
         let conn = self.get_connection();
-        let stmt = try!(conn.prepare(sql));
         let mut daos = vec![];
         let param = self.from_rust_type_tosql(params);
-        let rows = try!(stmt.query(&param));
-        for row in rows {
-            let columns = row.columns();
-            let mut index = 0;
-            let mut dao = Dao::new();
-            for c in columns {
-                let column_name = c.name();
-                let dtype = c.type_();
-                let rtype = self.from_sql_to_rust_type(&dtype, &row, index);
-                dao.set_value(column_name, rtype);
-                index += 1;
+        match self.fetch_size {
+            Some(fetch_size) if !self.in_transaction.get() => {
+                let stmt = try!(conn.prepare(sql));
+                // the portal which the rows are fetched from only lives in a transaction
+                let trans = try!(conn.transaction());
+                {
+                    let rows = try!(stmt.lazy_query(&trans, &param, fetch_size));
+                    for row in rows {
+                        let row = try!(row);
+                        daos.push(self.row_to_dao(&row));
+                    }
+                }
+                try!(trans.commit());
+            }
+            // the transaction started with begin is not known to the driver,
+            // so the rows are fetched from a cursor declared within it
+            Some(fetch_size) => {
+                let declare = format!("DECLARE rustorm_fetch NO SCROLL CURSOR FOR {}", sql);
+                try!(conn.execute(&declare, &param));
+                let fetch = try!(conn.prepare(&format!("FETCH FORWARD {} FROM rustorm_fetch",
+                                                       fetch_size)));
+                loop {
+                    let rows = try!(fetch.query(&[]));
+                    for row in &rows {
+                        daos.push(self.row_to_dao(&row));
+                    }
+                    if rows.len() < fetch_size as usize {
+                        break;
+                    }
+                }
+                try!(conn.execute("CLOSE rustorm_fetch", &[]));
+            }
+            None => {
+                let stmt = try!(conn.prepare(sql));
+                let rows = try!(stmt.query(&param));
+                for row in rows {
+                    daos.push(self.row_to_dao(&row));
+                }
             }
-            daos.push(dao);
         }
         Ok(daos)
     }
@@ -492,6 +1019,13 @@ impl Database for Postgres{
         Ok(result as usize)
     }
 
+    fn execute_batch(&self, sql: &str) -> Result<(), DbError> {
+        println!("SQL: \n{}", sql);
+        let conn = self.get_connection();
+        try!(conn.batch_execute(sql));
+        Ok(())
+    }
+
 }
 
 impl DatabaseDDL for Postgres{
@@ -503,17 +1037,82 @@ impl DatabaseDDL for Postgres{
     fn create_table(&self, model: &Table) {
     }
     fn build_create_table(&self, table: &Table) -> SqlFrag {
-        panic!("not yet")
+        let mut w = SqlFrag::new(self.sql_options());
+        w.append("CREATE TABLE ");
+        w.append(&table.complete_name());
+        w.append("(");
+        w.ln_tab();
+        let mut do_comma = false;
+        for c in &table.columns {
+            if do_comma {
+                w.commasp();
+                w.ln_tab();
+            } else {
+                do_comma = true;
+            }
+            w.column_name(&c.name);
+            w.append(" ");
+            w.append(&c.db_data_type);
+            match c.collation {
+                Some(ref collation) => self.sql_builder().build_collation(&mut w, collation),
+                None => (),
+            }
+            if c.is_identity {
+                w.append(" GENERATED ALWAYS AS IDENTITY");
+            }
+            if c.is_primary {
+                w.append(" PRIMARY KEY ");
+            }
+        }
+        for foreign_key in &table.foreign_keys {
+            w.commasp();
+            w.ln_tab();
+            self.sql_builder().build_foreign_key(&mut w, foreign_key);
+        }
+        w.ln();
+        w.append(")");
+        w
     }
     fn rename_table(&self, table: &Table, new_tablename: String) {
     }
     fn drop_table(&self, table: &Table) {
     }
     fn set_foreign_constraint(&self, model: &Table) {
+        for foreign_key in &model.foreign_keys {
+            let frag = self.sql_builder().build_add_foreign_key(model, foreign_key);
+            match self.execute_sql(&frag.sql, &vec![]) {
+                Ok(_) => println!("added foreign key.."),
+                Err(e) => panic!("foreign key not added {}", e),
+            }
+        }
     }
     fn set_primary_constraint(&self, model: &Table) {
     }
 
+    fn create_table_as(&self, name: &str, query: &Query) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_create_table_as(name, query));
+        try!(self.execute_sql(&frag.sql, &frag.params));
+        Ok(())
+    }
+
+    fn create_index(&self, table: &Table, index: &Index) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_create_index(table, index));
+        try!(self.execute_sql(&frag.sql, &frag.params));
+        Ok(())
+    }
+
+    fn comment_on_table(&self, table: &Table, comment: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_comment_on_table(table, comment));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
+    fn comment_on_column(&self, table: &Table, column: &Column, comment: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_comment_on_column(table, column, comment));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
 }
 
 /// this can be condensed with using just extracting the table definition
@@ -600,7 +1199,40 @@ impl DatabaseDev for Postgres{
             comment: comment,
             columns: columns,
             is_view: is_view,
+            foreign_keys: vec![],
+        }
+    }
+
+    fn get_enum_types(&self, schema: &str) -> Vec<(String, Vec<String>)> {
+        let sql = "
+            SELECT
+                pg_type.typname AS enum_name,
+                pg_enum.enumlabel AS enum_value
+             FROM pg_enum
+             INNER JOIN pg_type
+                ON pg_type.oid = pg_enum.enumtypid
+             INNER JOIN pg_namespace
+                ON pg_namespace.oid = pg_type.typnamespace
+             WHERE pg_namespace.nspname = $1
+             ORDER BY pg_type.typname, pg_enum.enumsortorder
+            ";
+        let conn = self.get_connection();
+        let stmt = conn.prepare(&sql).unwrap();
+        let mut enum_types: Vec<(String, Vec<String>)> = vec![];
+        for row in stmt.query(&[&schema]).unwrap() {
+            let enum_name: String = row.get("enum_name");
+            let enum_value: String = row.get("enum_value");
+            let is_same = match enum_types.last() {
+                Some(&(ref last, _)) => *last == enum_name,
+                None => false,
+            };
+            if is_same {
+                enum_types.last_mut().unwrap().1.push(enum_value);
+            } else {
+                enum_types.push((enum_name, vec![enum_value]));
+            }
         }
+        enum_types
     }
 
     fn get_all_tables(&self) -> Vec<(String, String, bool)> {
@@ -698,6 +1330,9 @@ impl DatabaseDev for Postgres{
             "double precision" | "numeric" => {
                 (vec![], "f64".to_string())
             }
+            "money" => {
+                (vec!["rustorm::dao::Money".to_string()], "Money".to_string())
+            }
             "name" | "character" | "character varying" | "text" | "citext" | "bpchar" => {
                 (vec![], "String".to_string())
             }
@@ -789,6 +1424,10 @@ impl DatabaseDev for Postgres{
             "f64" => {
                 "numeric".to_string()
             }
+            // stored as the minor units
+            "Money" => {
+                "bigint".to_string()
+            }
             "String" => {
                 "character varying".to_string()
             }
@@ -804,7 +1443,7 @@ impl DatabaseDev for Postgres{
             "NaiveDateTime" => {
                 "timestamp".to_string()
             }
-            "DateTime<UTC>" => {
+            "DateTime<UTC>" | "DateTime<FixedOffset>" => {
                 "timestamp with time zone".to_string()
             }
             "NaiveDate" => {
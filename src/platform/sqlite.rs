@@ -4,27 +4,66 @@ use dao::Dao;
 use dao::Value;
 use database::{Database, DatabaseDev};
 use writer::SqlFrag;
+use pool::ConnectionHook;
 use database::SqlOption;
 use rusqlite::SqliteConnection;
 use rusqlite::types::ToSql;
 use rusqlite::SqliteRow;
 use rusqlite::SqliteError;
-use table::{Table, Column, Foreign};
+use table::{Table, Column, Foreign, Index};
 use database::DatabaseDDL;
 use database::DbError;
+use database::DbErrorKind;
+use database::IsolationLevel;
+use database::Dialect;
 use r2d2::PooledConnection;
 use r2d2_sqlite::SqliteConnectionManager;
 use regex::Regex;
 use regex::Error as RegexError;
 use std::collections::BTreeMap;
+use std::cell::Cell;
 
 pub struct Sqlite {
     pool: Option<PooledConnection<SqliteConnectionManager>>,
+    /// when off, a new transaction is started when the previous one ends
+    auto_commit: Cell<bool>,
+    /// whether a transaction is started with begin
+    in_transaction: Cell<bool>,
+    /// hooks run before the connection is returned to the pool
+    on_release: Vec<ConnectionHook>,
 }
 
 impl From<SqliteError> for DbError {
     fn from(err: SqliteError) -> Self {
-        DbError::from_string(format!("{:?}", err))
+        let (kind, constraint) = classify_error(&err);
+        DbError::with_code(format!("{:?}", err), &format!("{}", err.code))
+            .with_constraint(constraint)
+            .with_kind(kind)
+    }
+}
+
+/// sqlite has no constraint names, the message tells the columns instead,
+/// ie: `UNIQUE constraint failed: category.name`.
+/// The extended result codes tell the kind of constraint, the primary code only
+/// tells SQLITE_CONSTRAINT, in which case the message is used
+fn classify_error(err: &SqliteError) -> (DbErrorKind, Option<String>) {
+    let columns = err.message.splitn(2, ": ").nth(1).map(|c| c.to_string());
+    let message = err.message.to_lowercase();
+    match err.code {
+        // SQLITE_CONSTRAINT_PRIMARYKEY, SQLITE_CONSTRAINT_UNIQUE
+        1555 | 2067 => (DbErrorKind::UniqueViolation(columns.clone()), columns),
+        // SQLITE_CONSTRAINT_FOREIGNKEY
+        787 => (DbErrorKind::ForeignKeyViolation(None), None),
+        // SQLITE_CONSTRAINT
+        19 if message.contains("unique") || message.contains("primary key") => {
+            (DbErrorKind::UniqueViolation(columns.clone()), columns)
+        }
+        19 if message.contains("foreign key") => (DbErrorKind::ForeignKeyViolation(None), None),
+        // SQLITE_BUSY_SNAPSHOT, the snapshot of the transaction is no longer the latest
+        517 => (DbErrorKind::SerializationFailure, None),
+        // SQLITE_BUSY, SQLITE_LOCKED and their extended codes, the busy timeout has passed
+        code if code & 0xff == 5 || code & 0xff == 6 => (DbErrorKind::Timeout, None),
+        _ => (DbErrorKind::Other(err.message.clone()), None),
     }
 }
 
@@ -34,14 +73,50 @@ impl From<RegexError> for DbError {
     }
 }
 
+impl Drop for Sqlite {
+    /// the connection goes back to the pool, where the next user expects no open transaction
+    fn drop(&mut self) {
+        if self.pool.is_some() {
+            if self.in_transaction.get() {
+                match self.execute_sql("ROLLBACK", &vec![]) {
+                    Ok(_) => self.in_transaction.set(false),
+                    Err(e) => println!("Unable to rollback the open transaction {}", e),
+                }
+            }
+            let db: &Database = self;
+            for hook in &self.on_release {
+                match hook(db) {
+                    Ok(_) => (),
+                    Err(e) => println!("Unable to run the release hook {}", e),
+                }
+            }
+        }
+    }
+}
+
 impl Sqlite{
 
     pub fn new() -> Self {
-        Sqlite { pool: None }
+        Sqlite {
+            pool: None,
+            auto_commit: Cell::new(true),
+            in_transaction: Cell::new(false),
+            on_release: vec![],
+        }
     }
 
     pub fn with_pooled_connection(pool: PooledConnection<SqliteConnectionManager>) -> Self {
-        Sqlite { pool: Some(pool) }
+        Sqlite {
+            pool: Some(pool),
+            auto_commit: Cell::new(true),
+            in_transaction: Cell::new(false),
+            on_release: vec![],
+        }
+    }
+
+    /// hooks run when this is dropped, before the connection is returned to the pool
+    pub fn on_release(&mut self, hooks: Vec<ConnectionHook>) {
+        self.on_release = hooks;
     }
 
     fn from_rust_type_tosql<'a>(&self, types: &'a Vec<Value>) -> Vec<&'a ToSql> {
@@ -51,6 +126,9 @@ impl Sqlite{
                 &Value::String(ref x) => {
                     params.push(x);
                 }
+                &Value::Money(ref x) => {
+                    params.push(&x.amount);
+                }
                 _ => panic!("not yet here {:?}", t),
             }
         }
@@ -299,15 +377,67 @@ impl Database for Sqlite{
             Err(e) => panic!(format!("{:?}", e)),
         }
     }
-    fn begin(&self) {
-    }
-    fn commit(&self) {
+    fn try_begin(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("BEGIN", &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
+    }
+    /// sqlite has no read only transaction, a deferred transaction
+    /// does not lock the database until the first read
+    fn begin_read_only(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("BEGIN DEFERRED TRANSACTION", &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
+    }
+    /// transactions in sqlite are always serializable, the level decides
+    /// when the database is locked: at the first read, at once for writing,
+    /// or at once for reading and writing
+    fn begin_with_isolation(&self, level: IsolationLevel) -> Result<(), DbError> {
+        let sql = match level {
+            IsolationLevel::ReadCommitted => "BEGIN DEFERRED TRANSACTION",
+            IsolationLevel::RepeatableRead => "BEGIN IMMEDIATE TRANSACTION",
+            IsolationLevel::Serializable => "BEGIN EXCLUSIVE TRANSACTION",
+        };
+        try!(self.execute_sql(sql, &vec![]));
+        self.in_transaction.set(true);
+        Ok(())
+    }
+    fn try_commit(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("COMMIT", &vec![]));
+        self.in_transaction.set(false);
+        if !self.auto_commit.get() {
+            try!(self.try_begin());
+        }
+        Ok(())
     }
-    fn rollback(&self) {
+    fn try_rollback(&self) -> Result<(), DbError> {
+        try!(self.execute_sql("ROLLBACK", &vec![]));
+        self.in_transaction.set(false);
+        if !self.auto_commit.get() {
+            try!(self.try_begin());
+        }
+        Ok(())
     }
     fn is_transacted(&self) -> bool {
         false
     }
+    fn in_transaction(&self) -> bool {
+        self.in_transaction.get()
+    }
+    /// sqlite is in auto commit mode outside of a transaction started with BEGIN
+    fn set_auto_commit(&self, auto_commit: bool) -> Result<(), DbError> {
+        if auto_commit == self.auto_commit.get() {
+            return Ok(());
+        }
+        if auto_commit {
+            try!(self.execute_sql("COMMIT", &vec![]));
+        } else {
+            try!(self.execute_sql("BEGIN", &vec![]));
+        }
+        self.in_transaction.set(!auto_commit);
+        self.auto_commit.set(auto_commit);
+        Ok(())
+    }
     fn is_closed(&self) -> bool {
         false
     }
@@ -327,11 +457,28 @@ impl Database for Sqlite{
         vec![
             SqlOption::UsesNumberedParam,  // uses numbered parameters
             SqlOption::SupportsCTE,
+            SqlOption::UsesGroupConcat,
+            SqlOption::UsesDatetimeModifier,
+            SqlOption::SupportsPartialIndex,
+            SqlOption::SupportsOnConflict,
+            SqlOption::SupportsLastInsertId,
         ]
     }
 
+    fn dialect(&self) -> Dialect {
+        Dialect::Sqlite
+    }
+
+    fn last_insert_id(&self) -> Result<Value, DbError> {
+        let sql = "SELECT last_insert_rowid() AS id";
+        match try!(self.execute_sql_with_one_return(sql, &vec![])) {
+            Some(dao) => Ok(dao.get_value("id")),
+            None => Err(DbError::new("No last insert rowid returned")),
+        }
+    }
+
     fn insert(&self, query: &Query) -> Result<Dao, DbError> {
-        let sql_frag = self.build_insert(query);
+        let sql_frag = try!(self.build_insert(query));
         match self.execute_sql_with_one_return(&sql_frag.sql, &sql_frag.params) {
             Ok(Some(result)) => Ok(result),
             Ok(None) => Err(DbError::new("No result from insert")),
@@ -409,6 +556,13 @@ impl Database for Sqlite{
         }
     }
 
+    fn execute_batch(&self, sql: &str) -> Result<(), DbError> {
+        println!("SQL: \n{}", sql);
+        let conn = self.get_connection();
+        try!(conn.execute_batch(sql));
+        Ok(())
+    }
+
 }
 
 impl DatabaseDDL for Sqlite{
@@ -457,16 +611,30 @@ impl DatabaseDDL for Sqlite{
             } else {
                 do_comma = true;
             }
-            w.append(&c.name);
+            w.column_name(&c.name);
             w.append(" ");
+            // only an integer primary key can be an auto increment in sqlite
+            if c.is_identity {
+                w.append("INTEGER PRIMARY KEY AUTOINCREMENT");
+                continue;
+            }
             let dt = self.rust_type_to_dbtype(&c.data_type);
             w.append(&dt);
+            match c.collation {
+                Some(ref collation) => self.sql_builder().build_collation(&mut w, collation),
+                None => (),
+            }
             if c.is_primary {
                 w.append(" PRIMARY KEY ");
             }
         }
         let fsql = build_foreign_key_stmt(table);
         w.append(&fsql.sql);
+        for foreign_key in &table.foreign_keys {
+            w.commasp();
+            w.ln_tab();
+            self.sql_builder().build_foreign_key(&mut w, foreign_key);
+        }
         w.ln();
         w.append(")");
         w
@@ -494,6 +662,30 @@ impl DatabaseDDL for Sqlite{
     fn set_primary_constraint(&self, model: &Table) {
         panic!("not yet");
     }
+
+    fn create_table_as(&self, name: &str, query: &Query) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_create_table_as(name, query));
+        try!(self.execute_sql(&frag.sql, &frag.params));
+        Ok(())
+    }
+
+    fn create_index(&self, table: &Table, index: &Index) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_create_index(table, index));
+        try!(self.execute_sql(&frag.sql, &frag.params));
+        Ok(())
+    }
+
+    fn comment_on_table(&self, table: &Table, comment: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_comment_on_table(table, comment));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
+
+    fn comment_on_column(&self, table: &Table, column: &Column, comment: &str) -> Result<(), DbError> {
+        let frag = try!(self.sql_builder().build_comment_on_column(table, column, comment));
+        try!(self.execute_sql(&frag.sql, &vec![]));
+        Ok(())
+    }
 }
 
 impl DatabaseDev for Sqlite{
@@ -541,6 +733,9 @@ impl DatabaseDev for Sqlite{
                         comment: column_comment,
                         not_null: not_null != "0",
                         is_inherited: false,
+                        collation: None,
+                        is_identity: false,
+                        is_generated: false,
                         foreign: column_foreign,
                     };
                     columns.push(column);
@@ -553,6 +748,7 @@ impl DatabaseDev for Sqlite{
                     comment: table_comment,
                     columns: columns,
                     is_view: false,
+                    foreign_keys: vec![],
                 }
             }
             Err(e) => {
@@ -585,6 +781,11 @@ impl DatabaseDev for Sqlite{
         vec![]
     }
 
+    /// sqlite has no enum types
+    fn get_enum_types(&self, schema: &str) -> Vec<(String, Vec<String>)> {
+        vec![]
+    }
+
     fn dbtype_to_rust_type(&self, db_type: &str) -> (Vec<String>, String) {
         panic!("not yet")
     }
@@ -614,3 +815,21 @@ CREATE TABLE product_availability (
     ";
     Sqlite::extract_comments(create_sql);
 }
+
+#[test]
+fn test_classify_error() {
+    let unique = SqliteError {
+        code: 19,
+        message: "UNIQUE constraint failed: category.name".to_string(),
+    };
+    let err = DbError::from(unique);
+    assert_eq!(err.kind(),
+               DbErrorKind::UniqueViolation(Some("category.name".to_string())));
+    assert_eq!(err.constraint(), Some("category.name"));
+
+    let busy = SqliteError {
+        code: 5,
+        message: "database is locked".to_string(),
+    };
+    assert_eq!(DbError::from(busy).kind(), DbErrorKind::Timeout);
+}
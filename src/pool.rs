@@ -11,6 +11,8 @@ use platform::Mysql;
 use mysql::conn::pool::MyPool;
 use mysql::conn::MyOpts;
 use database::DbError;
+use url::percent_encoding::{utf8_percent_encode, FORM_URLENCODED_ENCODE_SET};
+use std::sync::Arc;
 
 #[cfg(feature = "sqlite")]
 use r2d2_sqlite::SqliteConnectionManager;
@@ -47,6 +49,32 @@ impl Platform{
         }
     }
 
+    /// the concrete postgres platform, to use the driver connection directly
+    /// with `get_connection`, the connection is still returned to the pool when this is dropped
+    pub fn as_postgres(&self) -> Option<&Postgres> {
+        match *self {
+            Platform::Postgres(ref pg) => Some(pg),
+            _ => None,
+        }
+    }
+
+    /// the concrete sqlite platform, to use the driver connection directly
+    #[cfg(feature = "sqlite")]
+    pub fn as_sqlite(&self) -> Option<&Sqlite> {
+        match *self {
+            Platform::Sqlite(ref lite) => Some(lite),
+            _ => None,
+        }
+    }
+
+    /// the concrete mysql platform, to use the driver pool directly
+    pub fn as_mysql(&self) -> Option<&Mysql> {
+        match *self {
+            Platform::Mysql(ref my) => Some(my),
+            _ => None,
+        }
+    }
+
     pub fn as_dev(&self) -> &DatabaseDev {
         match *self {
             Platform::Postgres(ref pg) => pg,
@@ -57,32 +85,146 @@ impl Platform{
     }
 }
 
+/// a function run on a connection of the pool, ie: to set the search_path
+pub type ConnectionHook = Arc<Fn(&Database) -> Result<(), DbError> + Send + Sync>;
+
+/// options used when initializing the pool
+#[derive(Clone)]
+pub struct PoolConfig {
+    /// the number of connections in the pool
+    pub pool_size: usize,
+    /// the application_name of each connection, shown in pg_stat_activity (postgres)
+    pub application_name: Option<String>,
+    /// the number of idle connections spawned at init and maintained by the pool
+    pub min_idle: Option<usize>,
+    /// the character set of each connection, ie: utf8mb4 to store 4 byte characters (mysql)
+    pub charset: Option<String>,
+    /// run in order on the connection each time it is checked out with connect
+    pub on_acquire: Vec<ConnectionHook>,
+    /// run in order on the connection when it is dropped and returned to the pool
+    pub on_release: Vec<ConnectionHook>,
+}
+
+impl PoolConfig{
+
+    pub fn new(pool_size: usize) -> Self {
+        PoolConfig {
+            pool_size: pool_size,
+            application_name: None,
+            min_idle: None,
+            charset: None,
+            on_acquire: vec![],
+            on_release: vec![],
+        }
+    }
+
+    /// run the function on the connection each time it is checked out,
+    /// an error fails the checkout
+    pub fn on_acquire<F>(&mut self, hook: F) -> &mut Self
+        where F: Fn(&Database) -> Result<(), DbError> + Send + Sync + 'static
+    {
+        self.on_acquire.push(Arc::new(hook));
+        self
+    }
+
+    /// execute the statements on the connection each time it is checked out,
+    /// ie: `SET search_path TO bazaar`, `SET TIME ZONE 'UTC'`
+    pub fn on_acquire_sql(&mut self, sql: &str) -> &mut Self {
+        let sql = sql.to_string();
+        self.on_acquire(move |db| db.execute_batch(&sql))
+    }
+
+    /// run the function on the connection before it is returned to the pool,
+    /// an error is only printed since the connection is already dropped
+    pub fn on_release<F>(&mut self, hook: F) -> &mut Self
+        where F: Fn(&Database) -> Result<(), DbError> + Send + Sync + 'static
+    {
+        self.on_release.push(Arc::new(hook));
+        self
+    }
+
+    /// execute the statements on the connection before it is returned to the pool,
+    /// ie: `RESET ALL`
+    pub fn on_release_sql(&mut self, sql: &str) -> &mut Self {
+        let sql = sql.to_string();
+        self.on_release(move |db| db.execute_batch(&sql))
+    }
+}
+
+/// the hooks of the pool config, run around each checkout
+#[derive(Clone)]
+pub struct ConnectionHooks {
+    pub on_acquire: Vec<ConnectionHook>,
+    pub on_release: Vec<ConnectionHook>,
+}
+
+impl ConnectionHooks{
+
+    pub fn new() -> Self {
+        ConnectionHooks {
+            on_acquire: vec![],
+            on_release: vec![],
+        }
+    }
+
+    fn from_config(pool_config: &PoolConfig) -> Self {
+        ConnectionHooks {
+            on_acquire: pool_config.on_acquire.clone(),
+            on_release: pool_config.on_release.clone(),
+        }
+    }
+}
+
 /// Postgres, Sqlite uses r2d2 connection manager,
 /// Mysql has its own connection pooling
+/// each with the hooks run on the connections it hands out
 pub enum ManagedPool {
-    Postgres(Pool<PostgresConnectionManager>),
+    Postgres(Pool<PostgresConnectionManager>, ConnectionHooks),
     #[cfg(feature = "sqlite")]
-    Sqlite(Pool<SqliteConnectionManager>),
+    Sqlite(Pool<SqliteConnectionManager>, ConnectionHooks),
     Oracle,
-    Mysql(Option<MyPool>),
+    Mysql(Option<MyPool>, ConnectionHooks),
 }
 
 impl ManagedPool{
 
     /// initialize the pool
     pub fn init(url: &str, pool_size: usize) -> Result<Self, DbError> {
+        Self::init_with_config(url, &PoolConfig::new(pool_size))
+    }
+
+    /// initialize the pool with the options in the pool config
+    pub fn init_with_config(url: &str, pool_config: &PoolConfig) -> Result<Self, DbError> {
+        let pool_size = pool_config.pool_size;
+        let min_idle = pool_config.min_idle.map(|min_idle| min_idle as u32);
+        let hooks = ConnectionHooks::from_config(pool_config);
         let config = DbConfig::from_url(url);
         match config {
             Some(config) => {
                 let platform: &str = &config.platform;
                 match platform {
                     "postgres" => {
-                        let manager = PostgresConnectionManager::new(url, SslMode::None).unwrap();
+                        // extra parameters in the url are passed on the connection startup
+                        let url = match pool_config.application_name {
+                            Some(ref application_name) => {
+                                let separator = if url.contains("?") { "&" } else { "?" };
+                                // spaces, & and = in the name would break the query string
+                                let application_name =
+                                    utf8_percent_encode(application_name,
+                                                        FORM_URLENCODED_ENCODE_SET);
+                                format!("{}{}application_name={}", url, separator, application_name)
+                            }
+                            None => url.to_string(),
+                        };
+                        let manager = PostgresConnectionManager::new(&*url, SslMode::None).unwrap();
                         println!("Creating a connection with a pool size of {}", pool_size);
-                        let config = Config::builder().pool_size(pool_size as u32).build();
+                        let config = Config::builder()
+                                         .pool_size(pool_size as u32)
+                                         .min_idle(min_idle)
+                                         .build();
                         let pool = Pool::new(config, manager);
                         match pool {
-                            Ok(pool) => Ok(ManagedPool::Postgres(pool)),
+                            Ok(pool) => Ok(ManagedPool::Postgres(pool, hooks)),
                             Err(e) => {
                                 println!("Unable to create a pool");
                                 Err(DbError::new(&format!("{}", e)))
@@ -93,9 +235,12 @@ impl ManagedPool{
                     #[cfg(feature = "sqlite")]
                     "sqlite" => {
                         let manager = SqliteConnectionManager::new(&config.database).unwrap();
-                        let config = Config::builder().pool_size(pool_size as u32).build();
+                        let config = Config::builder()
+                                         .pool_size(pool_size as u32)
+                                         .min_idle(min_idle)
+                                         .build();
                         match Pool::new(config, manager) {
-                            Ok(pool) => Ok(ManagedPool::Sqlite(pool)),
+                            Ok(pool) => Ok(ManagedPool::Sqlite(pool, hooks)),
                             Err(e) => {
                                 println!("Unable to create a pool");
                                 Err(DbError::new(&format!("{}", e)))
@@ -104,16 +249,29 @@ impl ManagedPool{
 
                     }
                     "mysql" => {
+                        // issued on each new connection of the pool
+                        let init = match pool_config.charset {
+                            Some(ref charset) => {
+                                if !charset.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                                    return Err(DbError::new(&format!("Invalid charset {}",
+                                                                     charset)));
+                                }
+                                vec![format!("SET NAMES {}", charset)]
+                            }
+                            None => vec![],
+                        };
                         let opts = MyOpts {
                             user: config.username,
                             pass: config.password,
                             db_name: Some(config.database),
                             tcp_addr: Some(config.host.unwrap().to_string()),
                             tcp_port: config.port.unwrap_or(3306),
+                            init: init,
                             ..Default::default()
                         };
-                        match MyPool::new_manual(0, pool_size, opts) {
-                            Ok(pool) => Ok(ManagedPool::Mysql(Some(pool))),
+                        let min = pool_config.min_idle.unwrap_or(0);
+                        match MyPool::new_manual(min, pool_size, opts) {
+                            Ok(pool) => Ok(ManagedPool::Mysql(Some(pool), hooks)),
                             Err(e) => {
                                 println!("Unable to create a pool");
                                 Err(DbError::new(&format!("{}", e)))
@@ -132,37 +290,93 @@ impl ManagedPool{
 
     }
 
-    /// a conection is created here
-    pub fn connect(&self) -> Result<Platform, DbError> {
+    /// block until the min_idle connections of the pool are established,
+    /// so the first requests don't pay for the connection setup
+    pub fn warm_up(&self) -> Result<(), DbError> {
         match *self {
-            ManagedPool::Postgres(ref pool) => {
+            ManagedPool::Postgres(ref pool, _) => {
+                let min_idle = pool.config().min_idle().unwrap_or(0);
+                let mut connections = vec![];
+                // holding the connections forces the pool to establish them
+                while (connections.len() as u32) < min_idle {
+                    match pool.get() {
+                        Ok(conn) => connections.push(conn),
+                        Err(e) => {
+                            return Err(DbError::new(&format!("Unable to connect due to {}", e)))
+                        }
+                    }
+                }
+                Ok(())
+            }
+            #[cfg(feature = "sqlite")]
+            ManagedPool::Sqlite(ref pool, _) => {
+                let min_idle = pool.config().min_idle().unwrap_or(0);
+                let mut connections = vec![];
+                while (connections.len() as u32) < min_idle {
+                    match pool.get() {
+                        Ok(conn) => connections.push(conn),
+                        Err(e) => {
+                            return Err(DbError::new(&format!("Unable to connect due to {}", e)))
+                        }
+                    }
+                }
+                Ok(())
+            }
+            // mysql establishes the min connections when the pool is created
+            ManagedPool::Mysql(_, _) => Ok(()),
+            _ => Err(DbError::new("Any other database is not yet supported")),
+        }
+    }
+
+    /// the number of idle connections in the pool
+    pub fn idle_connections(&self) -> Option<usize> {
+        match *self {
+            ManagedPool::Postgres(ref pool, _) => Some(pool.state().idle_connections as usize),
+            #[cfg(feature = "sqlite")]
+            ManagedPool::Sqlite(ref pool, _) => Some(pool.state().idle_connections as usize),
+            _ => None,
+        }
+    }
+
+    /// a conection is created here, the on_acquire hooks are run on it
+    /// and the on_release hooks are run when it is dropped
+    pub fn connect(&self) -> Result<Platform, DbError> {
+        let (platform, hooks) = match *self {
+            ManagedPool::Postgres(ref pool, ref hooks) => {
                 match pool.get() {
                     Ok(conn) => {
-                        let pg = Postgres::with_pooled_connection(conn);
-                        Ok(Platform::Postgres(pg))
+                        let mut pg = Postgres::with_pooled_connection(conn);
+                        pg.on_release(hooks.on_release.clone());
+                        (Platform::Postgres(pg), hooks)
                     }
                     Err(e) => {
-                        Err(DbError::new(&format!("Unable to connect due to {}", e)))
+                        return Err(DbError::new(&format!("Unable to connect due to {}", e)))
                     }
                 }
             }
             #[cfg(feature = "sqlite")]
-            ManagedPool::Sqlite(ref pool) => {
+            ManagedPool::Sqlite(ref pool, ref hooks) => {
                 match pool.get() {
                     Ok(conn) => {
-                        let lite = Sqlite::with_pooled_connection(conn);
-                        Ok(Platform::Sqlite(lite))
+                        let mut lite = Sqlite::with_pooled_connection(conn);
+                        lite.on_release(hooks.on_release.clone());
+                        (Platform::Sqlite(lite), hooks)
                     }
                     Err(e) => {
-                        Err(DbError::new(&format!("Unable to connect due to {}", e)))
+                        return Err(DbError::new(&format!("Unable to connect due to {}", e)))
                     }
                 }
             }
-            ManagedPool::Mysql(ref pool) => {
-                let my = Mysql::with_pooled_connection(pool.clone().unwrap());// I hope cloning doesn't really clone the pool, just the Arc
-                Ok(Platform::Mysql(my))
+            ManagedPool::Mysql(ref pool, ref hooks) => {
+                let mut my = Mysql::with_pooled_connection(pool.clone().unwrap());// I hope cloning doesn't really clone the pool, just the Arc
+                my.on_release(hooks.on_release.clone());
+                (Platform::Mysql(my), hooks)
             }
-            _ => Err(DbError::new("Any other database is not yet supported")),
+            _ => return Err(DbError::new("Any other database is not yet supported")),
+        };
+        for hook in &hooks.on_acquire {
+            try!(hook(platform.as_ref()));
         }
+        Ok(platform)
     }
 }
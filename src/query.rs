@@ -1,14 +1,21 @@
-use dao::{Value, ToValue};
+use dao::{Value, ToValue, Array};
 use table::Table;
 use std::collections::BTreeMap;
 use database::Database;
-use dao::DaoResult;
+use dao::{DaoResult, PagedResult, CastIter};
+use dao::{DuplicateKey, FromValue, ExactFromValue};
+use std::collections::HashMap;
+use std::hash::Hash;
 use dao::IsDao;
 use dao::Dao;
 use table::IsTable;
-use writer::SqlFrag;
+use writer::{SqlFrag, KeywordCase};
 use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
 use database::DbError;
+use database::SqlOption;
+use builder::SqlBuilder;
 
 #[derive(Debug)]
 #[derive(Clone)]
@@ -33,6 +40,10 @@ pub struct Join {
     pub table_name: TableName,
     pub column1: Vec<String>,
     pub column2: Vec<String>,
+    /// join the rows of a subquery named as the table_name instead of a table
+    pub subquery: Option<Query>,
+    /// the subquery can refer to the columns of the preceding tables, ie: `LEFT JOIN LATERAL (..) AS latest ON true`
+    pub lateral: bool,
 }
 #[derive(Debug)]
 #[derive(Clone)]
@@ -62,10 +73,19 @@ pub enum Equality {
     GT, // GREATER_THAN,
     GTE, // GREATER_THAN_OR_EQUAL,
     IN,
+    /// the right operand is a list of values or a subquery,
+    /// no rows match when any of them is NULL, see `Query::filter_not_in_query`
     NOT_IN, // NOT_IN,
     LIKE,
     IS_NOT_NULL, // NOT_NULL,
     IS_NULL, // IS_NULL,
+    /// not equal, where NULL is compared as a value instead of making the condition unknown
+    IS_DISTINCT_FROM,
+    /// equal, where NULL is compared as a value instead of making the condition unknown
+    IS_NOT_DISTINCT_FROM,
+    /// the left operand is a raw sql condition,
+    /// the right operand holds the values of its `$n` placeholders
+    RAW,
 }
 
 /// function in a sql statement
@@ -74,6 +94,235 @@ pub enum Equality {
 pub struct Function {
     pub function: String,
     pub params: Vec<Operand>,
+    /// the window this function is evaluated over, ie: `OVER (PARTITION BY ..)`
+    pub over: Option<Window>,
+    /// aggregate only the distinct values, ie: `COUNT(DISTINCT user_id)`
+    pub distinct: bool,
+    /// the schema the function is defined in, ie: `analytics.compute(x)`
+    pub schema: Option<String>,
+    /// aggregate only the rows matching the condition, ie: `COUNT(*) FILTER (WHERE active)`
+    pub filter: Option<Box<Filter>>,
+}
+
+impl Function{
+
+    pub fn new(function: &str, params: Vec<Operand>) -> Self {
+        Function {
+            function: function.to_string(),
+            params: params,
+            over: None,
+            distinct: false,
+            schema: None,
+            filter: None,
+        }
+    }
+
+    /// call the function defined in this schema,
+    /// the schema is left out in databases that don't use schema
+    pub fn schema(mut self, schema: &str) -> Self {
+        self.schema = Some(schema.to_string());
+        self
+    }
+
+    /// aggregate only the distinct values of the params
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// aggregate only the rows matching the filter, rendered as `FILTER (WHERE ..)`,
+    /// or as `CASE WHEN .. THEN .. END` on the params where FILTER is not supported
+    pub fn filter_where(mut self, filter: Filter) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// ranking functions are only valid when used with an OVER clause
+    pub fn requires_over(&self) -> bool {
+        match &*self.function {
+            "ROW_NUMBER" | "RANK" | "DENSE_RANK" => true,
+            _ => false,
+        }
+    }
+
+    /// evaluate this function over the window
+    #[allow(non_snake_case)]
+    pub fn OVER(mut self, window: &Window) -> Self {
+        self.over = Some(window.clone());
+        self
+    }
+
+    /// use this function as a field renamed as alias
+    #[allow(non_snake_case)]
+    pub fn AS(self, alias: &str) -> Field {
+        Field {
+            operand: Operand::Function(self),
+            name: Some(alias.to_string()),
+        }
+    }
+}
+
+/// ROW_NUMBER() window function, needs an OVER clause
+#[allow(non_snake_case)]
+pub fn ROW_NUMBER() -> Function {
+    Function::new("ROW_NUMBER", vec![])
+}
+
+/// RANK() window function, needs an OVER clause
+#[allow(non_snake_case)]
+pub fn RANK() -> Function {
+    Function::new("RANK", vec![])
+}
+
+/// DENSE_RANK() window function, needs an OVER clause
+#[allow(non_snake_case)]
+pub fn DENSE_RANK() -> Function {
+    Function::new("DENSE_RANK", vec![])
+}
+
+/// COUNT(column)
+#[allow(non_snake_case)]
+pub fn COUNT(column: &str) -> Function {
+    Function::new("COUNT",
+                  vec![Operand::ColumnName(ColumnName::from_str(column))])
+}
+
+/// COUNT(DISTINCT column)
+#[allow(non_snake_case)]
+pub fn COUNT_DISTINCT(column: &str) -> Function {
+    COUNT(column).distinct()
+}
+
+/// SUM(column)
+#[allow(non_snake_case)]
+pub fn SUM(column: &str) -> Function {
+    Function::new("SUM",
+                  vec![Operand::ColumnName(ColumnName::from_str(column))])
+}
+
+/// LOWER(column)
+#[allow(non_snake_case)]
+pub fn LOWER(column: &str) -> Function {
+    Function::new("LOWER",
+                  vec![Operand::ColumnName(ColumnName::from_str(column))])
+}
+
+/// the condition holds for any of the rows of the subquery
+#[allow(non_snake_case)]
+pub fn ANY(query: Query) -> Operand {
+    Operand::Any(query)
+}
+
+/// the condition holds for all of the rows of the subquery
+#[allow(non_snake_case)]
+pub fn ALL(query: Query) -> Operand {
+    Operand::All(query)
+}
+
+/// concatenate the values of the column separated by `separator`,
+/// rendered as `STRING_AGG` or `GROUP_CONCAT` depending on the database
+#[allow(non_snake_case)]
+pub fn STRING_AGG(column: &str, separator: &str) -> Function {
+    Function::new("STRING_AGG",
+                  vec![Operand::ColumnName(ColumnName::from_str(column)),
+                       Operand::Value(Value::String(separator.to_string()))])
+}
+
+/// the window specification of a window function
+/// ie: OVER (PARTITION BY category_id ORDER BY price DESC)
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Window {
+    pub partition_by: Vec<Operand>,
+    pub order_by: Vec<(String, Direction)>,
+    /// the rows of the partition the function is evaluated on, ie: `ROWS BETWEEN ..`
+    pub frame: Option<Frame>,
+}
+
+/// whether the frame is counted in rows or in the values of the ORDER BY
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum FrameUnit {
+    ROWS,
+    RANGE,
+}
+
+/// the start or end of a window frame
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum FrameBound {
+    UNBOUNDED_PRECEDING,
+    /// the number of rows before the current row, ie: `3 PRECEDING`
+    PRECEDING(u64),
+    CURRENT_ROW,
+    /// the number of rows after the current row, ie: `3 FOLLOWING`
+    FOLLOWING(u64),
+    UNBOUNDED_FOLLOWING,
+}
+
+/// the frame of a window, ie: `ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW`
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Frame {
+    pub unit: FrameUnit,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl Window{
+
+    pub fn new() -> Self {
+        Window {
+            partition_by: vec![],
+            order_by: vec![],
+            frame: None,
+        }
+    }
+
+    /// the frame in rows, ie: `ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW` for a running total
+    pub fn rows_between(&mut self, start: FrameBound, end: FrameBound) -> &mut Self {
+        self.frame(FrameUnit::ROWS, start, end)
+    }
+
+    /// the frame in the values of the ORDER BY, the rows with the same value are in the same frame
+    pub fn range_between(&mut self, start: FrameBound, end: FrameBound) -> &mut Self {
+        self.frame(FrameUnit::RANGE, start, end)
+    }
+
+    fn frame(&mut self, unit: FrameUnit, start: FrameBound, end: FrameBound) -> &mut Self {
+        assert!(start != FrameBound::UNBOUNDED_FOLLOWING,
+                "The frame can not start at UNBOUNDED FOLLOWING");
+        assert!(end != FrameBound::UNBOUNDED_PRECEDING,
+                "The frame can not end at UNBOUNDED PRECEDING");
+        self.frame = Some(Frame {
+            unit: unit,
+            start: start,
+            end: end,
+        });
+        self
+    }
+
+    pub fn partition_by(&mut self, columns: Vec<&str>) -> &mut Self {
+        for c in columns {
+            let column_name = ColumnName::from_str(c);
+            self.partition_by.push(Operand::ColumnName(column_name));
+        }
+        self
+    }
+
+    ///ascending orderby of this column within the window
+    pub fn asc(&mut self, column: &str) -> &mut Self {
+        self.order_by.push((checked_column(column), Direction::ASC));
+        self
+    }
+
+    ///descending orderby of this column within the window
+    pub fn desc(&mut self, column: &str) -> &mut Self {
+        self.order_by.push((checked_column(column), Direction::DESC));
+        self
+    }
 }
 
 /// Operands can be columns, functions, query or value types
@@ -86,6 +335,21 @@ pub enum Operand {
     Query(Query),
     Value(Value),
     Vec(Vec<Operand>),
+    /// sql appended verbatim, this bypasses any escaping
+    Raw(String),
+    Case(Case),
+    /// a row value, ie: `(order_id, product_id)`
+    Row(Vec<Operand>),
+    /// the current timestamp minus the duration, ie: `NOW() - INTERVAL '3600 seconds'`
+    NowMinus(Duration),
+    /// compares to any of the rows of the subquery, ie: `= ANY (SELECT ..)`
+    Any(Query),
+    /// compares to all of the rows of the subquery, ie: `> ALL (SELECT ..)`
+    All(Query),
+    /// compares to any of the elements of an array parameter, ie: `= ANY($1)`
+    AnyArray(Value),
+    /// the default of the column, used in the values of insert and update
+    Default,
 }
 
 /// expression has left operand,
@@ -98,6 +362,48 @@ pub struct Condition {
     pub right: Operand,
 }
 
+impl Condition{
+
+    pub fn new(column: &str, equality: Equality, value: &ToValue) -> Self {
+        Condition {
+            left: Operand::ColumnName(ColumnName::from_str(column)),
+            equality: equality,
+            right: Operand::Value(value.to_db_type()),
+        }
+    }
+}
+
+/// CASE WHEN condition THEN operand .. ELSE operand END
+/// can be used as a field or as a parameter of a function
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Case {
+    pub whens: Vec<(Condition, Operand)>,
+    pub otherwise: Option<Box<Operand>>,
+}
+
+impl Case{
+
+    pub fn new() -> Self {
+        Case {
+            whens: vec![],
+            otherwise: None,
+        }
+    }
+
+    /// WHEN condition THEN operand
+    pub fn when(mut self, condition: Condition, then: Operand) -> Self {
+        self.whens.push((condition, then));
+        self
+    }
+
+    /// ELSE operand, when none of the conditions matched
+    pub fn otherwise(mut self, operand: Operand) -> Self {
+        self.otherwise = Some(Box::new(operand));
+        self
+    }
+}
+
 /// TODO: support for functions on columns
 /// TODO: need to merge with Expr
 #[derive(Debug)]
@@ -153,6 +459,22 @@ impl Filter{
         }
     }
 
+    /// the row of columns is in any of the rows of values
+    /// ie: `(order_id, product_id) IN ((1, 2), (3, 4))`
+    pub fn row_in(columns: Vec<&str>, rows: Vec<Vec<Value>>) -> Self {
+        let mut left = vec![];
+        for c in columns {
+            left.push(Operand::ColumnName(ColumnName::from_str(c)));
+        }
+        let mut right = vec![];
+        for row in rows {
+            assert!(row.len() == left.len(),
+                    "Each row should have a value for each of the columns");
+            let values = row.into_iter().map(|v| Operand::Value(v)).collect();
+            right.push(Operand::Row(values));
+        }
+        Filter::bare_new(Operand::Row(left), Equality::IN, Operand::Vec(right))
+    }
 
     pub fn is_null(column: &str) -> Self {
         Filter::new(column, Equality::IS_NULL, &())
@@ -189,6 +511,127 @@ impl Filter{
     }
 }
 
+/// conditions on the same column, each added to the query as a filter ANDed with the rest
+/// ie: `query.filter_on("price").gte(&10).lte(&100)`
+pub struct ColumnFilter<'a> {
+    query: &'a mut Query,
+    column: String,
+}
+
+impl <'a>ColumnFilter<'a>{
+
+    fn add(self, equality: Equality, value: &ToValue) -> Self {
+        self.query.add_filter(Filter::new(&self.column, equality, value));
+        self
+    }
+
+    pub fn eq(self, value: &ToValue) -> Self {
+        self.add(Equality::EQ, value)
+    }
+
+    pub fn neq(self, value: &ToValue) -> Self {
+        self.add(Equality::NEQ, value)
+    }
+
+    pub fn lt(self, value: &ToValue) -> Self {
+        self.add(Equality::LT, value)
+    }
+
+    pub fn lte(self, value: &ToValue) -> Self {
+        self.add(Equality::LTE, value)
+    }
+
+    pub fn gt(self, value: &ToValue) -> Self {
+        self.add(Equality::GT, value)
+    }
+
+    pub fn gte(self, value: &ToValue) -> Self {
+        self.add(Equality::GTE, value)
+    }
+}
+
+/// grouping set mode of the GROUP BY clause, used for subtotals
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum GroupingSet {
+    ROLLUP,
+    CUBE,
+}
+
+/// what to do when the inserted record conflicts with an existing one on the unique columns,
+/// ie: `ON CONFLICT (category_id) DO UPDATE SET name = EXCLUDED.name`
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct OnConflict {
+    /// the unique columns the conflict is detected on
+    pub columns: Vec<String>,
+    /// the columns of the existing record set to the values of the inserted record,
+    /// the conflicting record is left as is (`DO NOTHING`) when empty
+    pub update_columns: Vec<String>,
+}
+
+/// the limits a query has to be within to be considered safe to run,
+/// used to guard the queries built from the user input, ie: an ad-hoc query endpoint.
+/// A join without a condition is always rejected
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SafetyLimits {
+    /// the most tables joined
+    pub max_joins: Option<usize>,
+    /// reject the queries without a WHERE clause
+    pub require_filter: bool,
+    /// reject the selects with joins but without a LIMIT
+    pub require_limit_on_join: bool,
+}
+
+impl SafetyLimits{
+
+    /// only the joins without a condition are rejected
+    pub fn new() -> Self {
+        SafetyLimits {
+            max_joins: None,
+            require_filter: false,
+            require_limit_on_join: false,
+        }
+    }
+}
+
+/// the lock acquired on the selected rows, until the end of the transaction
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum LockMode {
+    /// `FOR UPDATE`, blocks the other updates, deletes and locks of the rows
+    UPDATE,
+    /// `FOR NO KEY UPDATE`, the inserts referring to the rows are not blocked (postgresql)
+    NO_KEY_UPDATE,
+    /// `FOR SHARE`, only blocks the updates and deletes of the rows
+    SHARE,
+    /// `FOR KEY SHARE`, only blocks the deletes and the key updates of the rows (postgresql)
+    KEY_SHARE,
+}
+
+/// how the rows of a table sample are picked
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum SampleMethod {
+    /// each row is picked with the probability of the percentage
+    BERNOULLI,
+    /// each page of the table is picked with the probability of the percentage,
+    /// faster but the rows of a page are picked together
+    SYSTEM,
+}
+
+/// a random sample of the rows of the table, ie: `TABLESAMPLE BERNOULLI (1)`
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct TableSample {
+    pub method: SampleMethod,
+    /// the percentage of the table, from 0 to 100
+    pub percent: f64,
+}
+
 /// Could have been SqlAction
 #[derive(Debug)]
 #[derive(Clone)]
@@ -210,6 +653,47 @@ pub struct ColumnName {
     pub schema: Option<String>,
 }
 
+/// a column handle that carries the rust type of the column,
+/// generated code declares these per table so filters are type checked at compile time,
+/// the mismatched types are checked in tests/compile-fail
+///
+/// ```
+/// use std::marker::PhantomData;
+/// use rustorm::query::{Query, TypedColumn, Equality};
+///
+/// const NAME: TypedColumn<String> = TypedColumn { name: "product.name", column_type: PhantomData };
+/// let mut query = Query::select_all();
+/// query.filter_column(&NAME, Equality::EQ, &"iphone".to_string());
+/// ```
+pub struct TypedColumn<T> {
+    pub name: &'static str,
+    pub column_type: PhantomData<T>,
+}
+
+impl <T>TypedColumn<T>{
+
+    pub fn to_column_name(&self) -> ColumnName {
+        ColumnName::from_str(self.name)
+    }
+
+    /// join on this column being equal to the other column,
+    /// columns of different types can't be compared
+    pub fn eq(&self, other: TypedColumn<T>) -> JoinCondition {
+        JoinCondition {
+            column1: self.name.to_string(),
+            column2: other.name.to_string(),
+        }
+    }
+}
+
+/// the columns compared in the ON clause of a join
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct JoinCondition {
+    pub column1: String,
+    pub column2: String,
+}
+
 #[derive(Debug)]
 #[derive(Clone)]
 pub struct Field {
@@ -237,24 +721,39 @@ impl Field{
 
 impl ColumnName{
 
+    /// parse `table.column` or `column`, the names are appended as is into the SQL,
+    /// so anything other than letters, digits, `_` and `$` has to be explicitly quoted,
+    /// otherwise this panics. Use the raw variants such as `Query::column_raw` for expressions
     pub fn from_str(column: &str) -> Self {
-        if column.contains(".") {
-            let splinters = column.split(".").collect::<Vec<&str>>();
-            assert!(splinters.len() == 2, "There should only be 2 splinters");
-            let table_split = splinters[0].to_string();
-            let column_split = splinters[1].to_string();
-            ColumnName {
-                column: column_split.to_string(),
-                table: Some(table_split.to_string()),
-                schema: None,
-            }
+        match ColumnName::try_from_str(column) {
+            Ok(column_name) => column_name,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// parse the name like `from_str`, but an invalid name is an error.
+    /// The column can be `*` to refer to all the columns of the table
+    pub fn try_from_str(column: &str) -> Result<Self, DbError> {
+        let splinters = split_identifier(column);
+        if splinters.len() > 2 {
+            return Err(DbError::from_string(format!("Invalid column name `{}`, \
+                                                     there should only be 2 splinters",
+                                                    column)));
+        }
+        let (table, name) = if splinters.len() == 2 {
+            try!(validate_identifier(&splinters[0]));
+            (Some(splinters[0].to_string()), &splinters[1])
         } else {
-            ColumnName {
-                column: column.to_string(),
-                table: None,
-                schema: None,
-            }
+            (None, &splinters[0])
+        };
+        if name != "*" {
+            try!(validate_identifier(name));
         }
+        Ok(ColumnName {
+            column: name.to_string(),
+            table: table,
+            schema: None,
+        })
     }
 
     fn default_rename(&self) -> String {
@@ -318,26 +817,43 @@ pub struct TableName {
 
 impl TableName{
 
+    /// parse `schema.table` or `table`, the names are appended as is into the SQL,
+    /// so anything other than letters, digits, `_` and `$` has to be explicitly quoted,
+    /// ie: `"order items"`, otherwise this panics
     pub fn from_str(str: &str) -> Self {
-        if str.contains(".") {
-            let splinters = str.split(".").collect::<Vec<&str>>();
-            assert!(splinters.len() == 2, "There should only be 2 splinters");
-            let schema_split = splinters[0].to_string();
-            let table_split = splinters[1].to_string();
-
-            TableName {
-                schema: Some(schema_split),
-                name: table_split,
-                columns: vec![],
-            }
+        match TableName::try_from_str(str) {
+            Ok(table_name) => table_name,
+            Err(e) => panic!("{}", e),
+        }
+    }
 
+    /// parse the name like `from_str`, but an invalid name is an error.
+    /// An empty schema, ie: `.product`, is the same as no schema
+    pub fn try_from_str(str: &str) -> Result<Self, DbError> {
+        let splinters = split_identifier(str);
+        if splinters.len() > 2 {
+            return Err(DbError::from_string(format!("Invalid table name `{}`, \
+                                                     there should only be 2 splinters",
+                                                    str)));
+        }
+        let (schema, name) = if splinters.len() == 2 {
+            (Some(&splinters[0]), &splinters[1])
         } else {
-            TableName {
-                schema: None,
-                name: str.to_string(),
-                columns: vec![],
+            (None, &splinters[0])
+        };
+        let schema = match schema {
+            Some(schema) if !schema.is_empty() => {
+                try!(validate_identifier(schema));
+                Some(schema.to_string())
             }
-        }
+            _ => None,
+        };
+        try!(validate_identifier(name));
+        Ok(TableName {
+            schema: schema,
+            name: name.to_string(),
+            columns: vec![],
+        })
     }
 
     pub fn complete_name(&self) -> String {
@@ -348,6 +864,66 @@ impl TableName{
     }
 }
 
+/// the column name as is, after checking it is a valid `table.column` or `column`
+fn checked_column(column: &str) -> String {
+    ColumnName::from_str(column);
+    column.to_string()
+}
+
+/// split a name on the dots, except the dots inside a quoted identifier
+fn split_identifier(name: &str) -> Vec<String> {
+    let mut splinters = vec![];
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in name.chars() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+                current.push(c);
+            }
+            None => {
+                match c {
+                    '.' => {
+                        splinters.push(current);
+                        current = String::new();
+                    }
+                    '"' | '`' => {
+                        quote = Some(c);
+                        current.push(c);
+                    }
+                    _ => current.push(c),
+                }
+            }
+        }
+    }
+    splinters.push(current);
+    splinters
+}
+
+/// an identifier is either plain: letters, digits, `_` and `$`,
+/// or wholly enclosed in double quotes or backticks with no other quote inside
+fn validate_identifier(identifier: &str) -> Result<(), DbError> {
+    let first = identifier.chars().next();
+    let last = identifier.chars().last();
+    let is_quoted = match first {
+        Some(quote) if quote == '"' || quote == '`' => {
+            identifier.len() >= 2 && last == Some(quote) &&
+            !identifier[1..identifier.len() - 1].contains(quote)
+        }
+        _ => false,
+    };
+    let is_plain = !identifier.is_empty() &&
+                   identifier.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if is_quoted || is_plain {
+        Ok(())
+    } else {
+        Err(DbError::from_string(format!("Invalid identifier `{}`, quote it if it is intended",
+                                         identifier)))
+    }
+}
+
 impl PartialEq for TableName{
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name && self.schema == other.schema
@@ -387,19 +963,25 @@ impl ToTableName for str{
 
 impl ToTableName for Table{
 
-    /// contain the columns for later use when renaming is necessary
+    /// contain the columns for later use when renaming is necessary,
+    /// an empty schema, as in sqlite, is no schema
     fn to_table_name(&self) -> TableName {
+        let schema = if self.schema.is_empty() {
+            None
+        } else {
+            Some(self.schema.to_string())
+        };
         let mut columns = vec![];
         for c in &self.columns {
             let column_name = ColumnName {
-                schema: Some(self.schema.to_string()),
+                schema: schema.clone(),
                 table: Some(self.name.to_string()),
                 column: c.name.to_string(),
             };
             columns.push(column_name);
         }
         TableName {
-            schema: Some(self.schema.to_string()),
+            schema: schema,
             name: self.name.to_string(),
             columns: columns,
         }
@@ -446,11 +1028,17 @@ pub struct Query{
     /// needs to support expressions
     pub order_by:Vec<(String, Direction)>,
 
+    /// the collation used when ordering by the column, ie: name => und-x-icu
+    pub order_by_collations: BTreeMap<String, String>,
+
     /// grouping columns to create an aggregate
     pub group_by: Vec<Operand>,
 
-    /// having field
-    pub having: Vec<Condition>,
+    /// when set, the group by columns are grouped using ROLLUP or CUBE
+    pub grouping_set: Option<GroupingSet>,
+
+    /// having field, joined with AND just like the filters
+    pub having: Vec<Filter>,
 
     /// exclude the mention of the columns in the SQL query, useful when ignoring changes in update/insert records
     pub excluded_columns:Vec<ColumnName>,
@@ -474,6 +1062,66 @@ pub struct Query{
 
     /// the returning clause of the query when supported,
     pub enumerated_returns: Vec<Field>,
+
+    /// the case of the keywords in the built sql
+    pub keyword_case: KeywordCase,
+
+    /// bind identical values only once and reuse their placeholder
+    pub reuse_params: bool,
+
+    /// whether this query is used inside another query,
+    /// columns are rendered with their table names to allow correlated references
+    pub is_subquery: bool,
+
+    /// the boolean column which flags the soft deleted records,
+    /// only the records with this column set to true are selected
+    pub soft_delete_column: Option<String>,
+
+    /// include the soft deleted records when selecting
+    pub with_inactive: bool,
+
+    /// written as a leading comment of the sql, ie: `/* tenant:42 */`
+    pub tag: Option<String>,
+
+    /// select only from the table, excluding the records of the tables inheriting it
+    pub only: bool,
+
+    /// insert a record where every column is set to its default
+    pub default_values: bool,
+
+    /// filters on the aliased fields of this query,
+    /// applied to an outer query which wraps this query
+    pub alias_filters: Vec<Filter>,
+
+    /// the rows of a bare `VALUES (..), (..)` table expression
+    pub value_rows: Vec<Vec<Operand>>,
+
+    /// the statement is cancelled when it runs longer than this,
+    /// the timeout of the connection is restored after the statement
+    pub timeout: Option<Duration>,
+
+    /// insert with `REPLACE INTO`, the existing record with the same key is deleted first (mysql)
+    pub replace: bool,
+
+    /// the columns paired with the values to insert,
+    /// used instead of aligning the enumerated fields with the values
+    pub column_values: Vec<(String, Operand)>,
+
+    /// lock the selected rows, ie: `FOR UPDATE`
+    pub lock: Option<LockMode>,
+
+    /// fail right away when the rows are locked by another transaction instead of waiting
+    pub nowait: bool,
+
+    /// select from a random sample of the rows of the table
+    pub sample: Option<TableSample>,
+
+    /// upsert, when the inserted record conflicts with an existing one
+    pub on_conflict: Option<OnConflict>,
+
+    /// include whether the upserted record was inserted or updated in the returned record,
+    /// as the boolean `inserted`
+    pub return_inserted: bool,
 }
 
 impl Query{
@@ -490,7 +1138,9 @@ impl Query{
             filters: vec![],
             joins: vec![],
             order_by: vec![],
+            order_by_collations: BTreeMap::new(),
             group_by: vec![],
+            grouping_set: None,
             having: vec![],
             excluded_columns: vec![],
             page: None,
@@ -498,6 +1148,24 @@ impl Query{
             from: None,
             values: vec![],
             enumerated_returns: vec![],
+            keyword_case: KeywordCase::Upper,
+            reuse_params: false,
+            is_subquery: false,
+            soft_delete_column: None,
+            with_inactive: false,
+            tag: None,
+            only: false,
+            default_values: false,
+            alias_filters: vec![],
+            value_rows: vec![],
+            timeout: None,
+            replace: false,
+            column_values: vec![],
+            lock: None,
+            nowait: false,
+            sample: None,
+            on_conflict: None,
+            return_inserted: false,
         }
     }
 
@@ -523,6 +1191,168 @@ impl Query{
         q
     }
 
+    /// copy this query to build a variant of it, such as adding more filters or a different limit
+    /// the fork owns all of its parts, changes on it don't affect the original query
+    pub fn fork(&self) -> Query {
+        self.clone()
+    }
+
+    /// write the keywords in upper case (default) or lower case
+    pub fn keyword_case(&mut self, keyword_case: KeywordCase) -> &mut Self {
+        self.keyword_case = keyword_case;
+        self
+    }
+
+    /// bind an identical value only once, every use of it refers to the same placeholder
+    /// ie: `created > $1 AND updated > $1`
+    pub fn reuse_params(&mut self) -> &mut Self {
+        self.reuse_params = true;
+        self
+    }
+
+    /// cancel this query when it runs longer than the timeout,
+    /// the other queries on the connection are not affected.
+    /// mysql only applies the timeout to select statements
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// lock the selected rows until the end of the transaction, ie: `FOR UPDATE`
+    pub fn lock(&mut self, lock: LockMode) -> &mut Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    /// `FOR UPDATE`
+    pub fn for_update(&mut self) -> &mut Self {
+        self.lock(LockMode::UPDATE)
+    }
+
+    /// `FOR NO KEY UPDATE`, the lock taken by the updates not changing the key (postgresql)
+    pub fn for_no_key_update(&mut self) -> &mut Self {
+        self.lock(LockMode::NO_KEY_UPDATE)
+    }
+
+    /// `FOR SHARE`
+    pub fn for_share(&mut self) -> &mut Self {
+        self.lock(LockMode::SHARE)
+    }
+
+    /// the lock errors right away when the rows are already locked, ie: `FOR UPDATE NOWAIT`
+    pub fn nowait(&mut self) -> &mut Self {
+        self.nowait = true;
+        self
+    }
+
+    /// select from a random sample of about `percent` of the rows of the table,
+    /// ie: `FROM product TABLESAMPLE BERNOULLI (1)` (postgresql)
+    pub fn sample(&mut self, method: SampleMethod, percent: f64) -> &mut Self {
+        assert!(percent >= 0.0 && percent <= 100.0,
+                "The sample percentage should be from 0 to 100");
+        self.sample = Some(TableSample {
+            method: method,
+            percent: percent,
+        });
+        self
+    }
+
+    /// the records of the table are soft deleted by setting this column to false,
+    /// selecting will only return the records where the column is true
+    pub fn soft_delete(&mut self, column: &str) -> &mut Self {
+        self.soft_delete_column = Some(checked_column(column));
+        self
+    }
+
+    /// also select the records that are soft deleted
+    pub fn with_inactive(&mut self) -> &mut Self {
+        self.with_inactive = true;
+        self
+    }
+
+    /// the filters used when selecting, including the soft delete filter when applicable
+    pub fn select_filters(&self) -> Vec<Filter> {
+        let mut filters = self.filters.clone();
+        match self.soft_delete_column {
+            Some(ref column) if !self.with_inactive => {
+                let mut filter = Filter::new(column, Equality::EQ, &true);
+                // qualified with the table selected from, a joined table could have the same column
+                if !column.contains(".") {
+                    let table = match self.from {
+                        Some(ref field) => {
+                            match field.name {
+                                Some(ref alias) => Some(alias.to_string()),
+                                None => self.get_from_table().map(|table| table.name.to_string()),
+                            }
+                        }
+                        None => None,
+                    };
+                    filter.condition.left = Operand::ColumnName(ColumnName {
+                        column: column.to_string(),
+                        table: table,
+                        schema: None,
+                    });
+                }
+                filters.push(filter);
+            }
+            _ => (),
+        }
+        filters
+    }
+
+    /// check the query is within the limits before executing it,
+    /// the reason is returned when it is not
+    pub fn validate_safety(&self, limits: &SafetyLimits) -> Result<(), String> {
+        for join in &self.joins {
+            // a lateral subquery is filtered by the columns it refers to
+            let is_cartesian = match join.join_type {
+                Some(JoinType::CROSS) => true,
+                _ => join.column1.is_empty() && !join.lateral,
+            };
+            if is_cartesian {
+                return Err(format!("The join of {} has no condition, it is a cartesian product",
+                                   join.table_name));
+            }
+        }
+        match limits.max_joins {
+            Some(max_joins) if self.joins.len() > max_joins => {
+                return Err(format!("The query joins {} tables, the most allowed is {}",
+                                   self.joins.len(),
+                                   max_joins));
+            }
+            _ => (),
+        }
+        match self.sql_type {
+            SqlType::INSERT => (),
+            _ if limits.require_filter && self.filters.is_empty() => {
+                return Err("The query has no WHERE clause".to_string());
+            }
+            _ => (),
+        }
+        match self.sql_type {
+            SqlType::SELECT if limits.require_limit_on_join && !self.joins.is_empty() &&
+                               self.page_size.is_none() => {
+                return Err("The joined select has no LIMIT".to_string());
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// prepend a comment to the sql, useful for tracing the query in the slow query logs
+    /// and for optimizer hints
+    pub fn tag(&mut self, tag: &str) -> &mut Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// exclude the records of the tables inheriting the table, ie: `SELECT * FROM ONLY product`
+    /// ignored in databases that don't support inheritance
+    pub fn only(&mut self) -> &mut Self {
+        self.only = true;
+        self
+    }
+
     /// add DISTINCT ie: SELECT DISTINCT
     pub fn distinct(&mut self) -> &mut Self {
         self.distinct = true;
@@ -534,6 +1364,19 @@ impl Query{
         q.all();
         q
     }
+    /// a table expression of literal rows, ie: `VALUES ($1, $2), ($3, $4)`,
+    /// used as a subquery in FROM, the columns are named by the database (`column1`, `column2` in postgres)
+    pub fn values_table(rows: &Vec<Vec<Value>>) -> Self {
+        assert!(!rows.is_empty(), "There should be rows in the values table");
+        let width = rows[0].len();
+        let mut q = Self::select();
+        for row in rows {
+            assert!(row.len() == width, "Each row should have the same number of values");
+            q.value_rows.push(row.iter().map(|value| Operand::Value(value.clone())).collect());
+        }
+        q
+    }
+
     pub fn enumerate_all() -> Self {
         let mut q = Self::select();
         q.enumerate_all = true;
@@ -566,6 +1409,16 @@ impl Query{
     }
 
 
+    /// select an expression that is not a plain column, ie: `count(*)`.
+    /// The sql is appended as is, never put user input in it.
+    pub fn column_raw(&mut self, sql: &str) -> &mut Self {
+        let field = Field {
+            operand: Operand::Raw(sql.to_string()),
+            name: None,
+        };
+        self.field(field)
+    }
+
     pub fn columns(&mut self, columns: Vec<&str>) -> &mut Self {
         for c in columns {
             self.column(c);
@@ -573,6 +1426,22 @@ impl Query{
         self
     }
 
+    /// add a field to select, such as functions which are renamed
+    pub fn field(&mut self, field: Field) -> &mut Self {
+        self.enumerated_fields.push(field);
+        self
+    }
+
+    /// select the result of a scalar subquery as a column renamed as alias
+    /// ie: (SELECT count(*) FROM orders WHERE orders.user_id = users.user_id) AS order_count
+    pub fn subquery(&mut self, query: Query, alias: &str) -> &mut Self {
+        let field = Field {
+            operand: Operand::Query(query),
+            name: Some(alias.to_string()),
+        };
+        self.field(field)
+    }
+
     pub fn group_by(&mut self, columns: Vec<&str>) -> &mut Self {
         for c in columns {
             let column_name = ColumnName::from_str(c);
@@ -582,15 +1451,34 @@ impl Query{
         self
     }
 
+    /// GROUP BY ROLLUP (columns), for subtotals on each level of the columns
+    pub fn group_by_rollup(&mut self, columns: Vec<&str>) -> &mut Self {
+        self.grouping_set = Some(GroupingSet::ROLLUP);
+        self.group_by(columns)
+    }
+
+    /// GROUP BY CUBE (columns), for subtotals on all combinations of the columns
+    pub fn group_by_cube(&mut self, columns: Vec<&str>) -> &mut Self {
+        self.grouping_set = Some(GroupingSet::CUBE);
+        self.group_by(columns)
+    }
+
     pub fn having(&mut self, column: &str, equality: Equality, value: &ToValue) -> &mut Self {
-        let column_name = ColumnName::from_str(column);
-        let left = Operand::ColumnName(column_name);
-        let cond = Condition {
-            left: left,
-            equality: equality,
-            right: Operand::Value(value.to_db_type()),
-        };
-        self.having.push(cond);
+        self.add_having(Filter::new(column, equality, value))
+    }
+
+    /// a HAVING condition on an aggregate expression, ie: `count(*) > $1`.
+    /// The sql is appended as is, never put user input in it.
+    pub fn having_raw(&mut self, sql: &str, equality: Equality, value: &ToValue) -> &mut Self {
+        let filter = Filter::bare_new(Operand::Raw(sql.to_string()),
+                                      equality,
+                                      Operand::Value(value.to_db_type()));
+        self.add_having(filter)
+    }
+
+    /// add a filter to the HAVING clause, use the filter's `or` for OR conditions
+    pub fn add_having(&mut self, filter: Filter) -> &mut Self {
+        self.having.push(filter);
         self
     }
 
@@ -659,6 +1547,42 @@ impl Query{
         self.sql_type = SqlType::INSERT;
         self.from(table)
     }
+
+    /// insert the record replacing the existing record with the same primary or unique key,
+    /// ie: `REPLACE INTO product ..`, only supported in mysql
+    pub fn replace_into(&mut self) -> &mut Self {
+        self.replace = true;
+        self
+    }
+
+    /// upsert, when a record with the same values on the columns exists
+    /// update its update_columns to the values being inserted instead
+    pub fn on_conflict_update(&mut self, columns: Vec<&str>, update_columns: Vec<&str>) -> &mut Self {
+        self.on_conflict = Some(OnConflict {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            update_columns: update_columns.iter().map(|c| c.to_string()).collect(),
+        });
+        self
+    }
+
+    /// skip inserting the record when a record with the same values on the columns exists
+    pub fn on_conflict_do_nothing(&mut self, columns: Vec<&str>) -> &mut Self {
+        self.on_conflict_update(columns, vec![])
+    }
+
+    /// return `inserted`, true when the upserted record was inserted, false when updated
+    pub fn return_inserted(&mut self) -> &mut Self {
+        self.return_inserted = true;
+        self
+    }
+
+    /// insert a record where every column is set to its default,
+    /// ie: `INSERT INTO t DEFAULT VALUES`
+    pub fn default_values(&mut self) -> &mut Self {
+        self.default_values = true;
+        self
+    }
+
     /// can not use into since it's rust .into built-in (owned)
     pub fn into_table(&mut self, table: &str) -> &mut Self {
         self.into_(&table)
@@ -729,8 +1653,10 @@ impl Query{
             modifier: Some(Modifier::LEFT),
             join_type: None,
             table_name: table.to_table_name(),
-            column1: vec![column1.to_string()],
-            column2: vec![column2.to_string()],
+            column1: vec![checked_column(column1)],
+            column2: vec![checked_column(column2)],
+            subquery: None,
+            lateral: false,
         };
         self.join(join)
     }
@@ -742,8 +1668,10 @@ impl Query{
             modifier: Some(Modifier::RIGHT),
             join_type: None,
             table_name: table.to_table_name(),
-            column1: vec![column1.to_string()],
-            column2: vec![column2.to_string()],
+            column1: vec![checked_column(column1)],
+            column2: vec![checked_column(column2)],
+            subquery: None,
+            lateral: false,
         };
         self.join(join)
     }
@@ -755,8 +1683,10 @@ impl Query{
             modifier: Some(Modifier::FULL),
             join_type: None,
             table_name: table.to_table_name(),
-            column1: vec![column1.to_string()],
-            column2: vec![column2.to_string()],
+            column1: vec![checked_column(column1)],
+            column2: vec![checked_column(column2)],
+            subquery: None,
+            lateral: false,
         };
         self.join(join)
     }
@@ -769,20 +1699,75 @@ impl Query{
             modifier: None,
             join_type: Some(JoinType::INNER),
             table_name: table.to_table_name(),
-            column1: vec![column1.to_string()],
-            column2: vec![column2.to_string()],
+            column1: vec![checked_column(column1)],
+            column2: vec![checked_column(column2)],
+            subquery: None,
+            lateral: false,
+        };
+        self.join(join)
+    }
+
+    /// left join the table on the condition of the typed columns,
+    /// ie: `.left_join_on(&"bazaar.product_category", product::product_id.eq(product_category::product_id))`
+    pub fn left_join_on(&mut self, table: &ToTableName, condition: JoinCondition) -> &mut Self {
+        self.left_join(table, &condition.column1, &condition.column2)
+    }
+
+    pub fn inner_join_on(&mut self, table: &ToTableName, condition: JoinCondition) -> &mut Self {
+        self.inner_join(table, &condition.column1, &condition.column2)
+    }
+
+    /// one more condition of the last joined table, joined with AND
+    #[allow(non_snake_case)]
+    pub fn ON(&mut self, condition: JoinCondition) -> &mut Self {
+        let join = self.joins.last_mut();
+        assert!(join.is_some(), "There should be a joined table for the ON condition");
+        let join = join.unwrap();
+        join.column1.push(checked_column(&condition.column1));
+        join.column2.push(checked_column(&condition.column2));
+        self
+    }
+
+    /// left join the rows of the subquery evaluated for each row of the preceding tables,
+    /// ie: `LEFT JOIN LATERAL (SELECT .. WHERE orders.user_id = users.user_id LIMIT 1) AS latest ON true`
+    pub fn left_join_lateral(&mut self, query: Query, alias: &str) -> &mut Self {
+        let join = Join {
+            modifier: Some(Modifier::LEFT),
+            join_type: None,
+            table_name: TableName::from_str(alias),
+            column1: vec![],
+            column2: vec![],
+            subquery: Some(query),
+            lateral: true,
         };
         self.join(join)
     }
 
     ///ascending orderby of this column
     pub fn asc(&mut self, column: &str) -> &mut Self {
-        self.order_by.push((column.to_string(), Direction::ASC));
+        self.order_by.push((checked_column(column), Direction::ASC));
         self
     }
     ///ascending orderby of this column
     pub fn desc(&mut self, column: &str) -> &mut Self {
-        self.order_by.push((column.to_string(), Direction::DESC));
+        self.order_by.push((checked_column(column), Direction::DESC));
+        self
+    }
+
+    /// order by the selected field at this position, starting from 1, ie: `ORDER BY 2 DESC`
+    /// avoids repeating a complex expression of the selected field
+    pub fn order_by_position(&mut self, position: usize, direction: Direction) -> &mut Self {
+        self.order_by.push((position.to_string(), direction));
+        self
+    }
+
+    /// order this column using the collation, ie: `ORDER BY name COLLATE "und-x-icu" ASC`
+    /// useful for a case insensitive ordering
+    pub fn collate(&mut self, column: &str, collation: &str) -> &mut Self {
+        assert!(!collation.contains('"') && !collation.contains('`'),
+                "Invalid collation `{}`",
+                collation);
+        self.order_by_collations.insert(checked_column(column), collation.to_string());
         self
     }
 
@@ -851,7 +1836,8 @@ impl Query{
         for i in excluded_columns {
             self.remove_from_enumerated(&i);
         }
-        if self.excluded_columns.is_empty() && self.enumerated_fields.is_empty() {
+        if self.excluded_columns.is_empty() && self.enumerated_fields.is_empty() &&
+           self.column_values.is_empty() {
             self.all();
         }
         self
@@ -981,6 +1967,121 @@ impl Query{
         self.add_filter(Filter::new(column, equality, value))
     }
 
+    /// filter on an alias of a selected field such as an aggregate or a computed expression,
+    /// which can not be referred to in the WHERE clause of the same query.
+    /// The query is wrapped as a subquery and the filter is applied outside of it:
+    /// `SELECT * FROM (...) AS aliased WHERE total > $1`
+    pub fn filter_on_alias(&mut self, alias: &str, equality: Equality, value: &ToValue) -> &mut Self {
+        self.alias_filters.push(Filter::new(alias, equality, value));
+        self
+    }
+
+    /// the outer query which applies the alias filters on this query,
+    /// the ordering and paging are moved to the outer query since they can refer to the aliases too
+    pub fn wrap_alias_filters(&self) -> Query {
+        let mut inner = self.clone();
+        inner.alias_filters = vec![];
+        inner.order_by = vec![];
+        inner.order_by_collations = BTreeMap::new();
+        inner.page = None;
+        inner.page_size = None;
+        inner.tag = None;
+
+        let mut outer = Query::select_all();
+        outer.keyword_case = self.keyword_case.clone();
+        outer.reuse_params = self.reuse_params;
+        outer.tag = self.tag.clone();
+        outer.from_query(inner, "aliased");
+        outer.filters = self.alias_filters.clone();
+        outer.order_by = self.order_by.clone();
+        outer.order_by_collations = self.order_by_collations.clone();
+        outer.page = self.page;
+        outer.page_size = self.page_size;
+        outer
+    }
+
+    /// chain several conditions on the column without repeating it
+    pub fn filter_on(&mut self, column: &str) -> ColumnFilter {
+        ColumnFilter {
+            query: self,
+            column: column.to_string(),
+        }
+    }
+
+    /// filter using a typed column, the value should be of the same type as the column
+    pub fn filter_column<T: ToValue>(&mut self,
+                                     column: &TypedColumn<T>,
+                                     equality: Equality,
+                                     value: &T)
+                                     -> &mut Self {
+        self.add_filter(Filter::new(column.name, equality, value))
+    }
+
+    /// the column is within the last duration until now, ie: `created >= NOW() - INTERVAL '..'`
+    pub fn filter_since(&mut self, column: &str, duration: Duration) -> &mut Self {
+        let left = Operand::ColumnName(ColumnName::from_str(column));
+        self.add_filter(Filter::bare_new(left, Equality::GTE, Operand::NowMinus(duration)))
+    }
+
+    /// compare the column against the rows of a subquery,
+    /// wrapped in `ANY` or `ALL` ie: `price > ALL (SELECT ..)`
+    pub fn filter_subquery(&mut self, column: &str, equality: Equality, right: Operand) -> &mut Self {
+        let left = Operand::ColumnName(ColumnName::from_str(column));
+        self.add_filter(Filter::bare_new(left, equality, right))
+    }
+
+    /// the column matches any of the rows of the subquery, ie: `id IN (SELECT ..)`
+    pub fn filter_in_query(&mut self, column: &str, query: Query) -> &mut Self {
+        self.filter_subquery(column, Equality::IN, Operand::Query(query))
+    }
+
+    /// the column matches none of the rows of the subquery, ie: `id NOT IN (SELECT ..)`
+    ///
+    /// beware: when the subquery returns a NULL, `NOT IN` is never true and no rows are returned.
+    /// Filter the NULLs out in the subquery with `IS NOT NULL`,
+    /// or use a correlated `NOT EXISTS (SELECT ..)` with `filter_raw` instead
+    pub fn filter_not_in_query(&mut self, column: &str, query: Query) -> &mut Self {
+        self.filter_subquery(column, Equality::NOT_IN, Operand::Query(query))
+    }
+
+    /// the column matches any of the values, bound as a single array parameter
+    /// ie: `id = ANY($1)` instead of expanding `id IN ($1, $2, ..)`,
+    /// so the statement stays the same whatever the number of values.
+    /// No values matches no rows, the element type of an empty array is unknown
+    pub fn filter_any_array<T: ToValue>(&mut self, column: &str, values: &[T]) -> &mut Self {
+        if values.is_empty() {
+            return self.filter_raw("1 = 0", vec![]);
+        }
+        let values = values.iter().map(|v| v.to_db_type()).collect();
+        let array = Value::Array(Array::from_values(values));
+        self.filter_subquery(column, Equality::EQ, Operand::AnyArray(array))
+    }
+
+    /// filter the composite of columns by a list of rows,
+    /// useful for looking up records with composite keys
+    pub fn filter_row_in(&mut self, columns: Vec<&str>, rows: Vec<Vec<&ToValue>>) -> &mut Self {
+        let mut values = vec![];
+        for row in rows {
+            values.push(row.iter().map(|v| v.to_db_type()).collect());
+        }
+        self.add_filter(Filter::row_in(columns, values))
+    }
+
+    /// filter using a raw sql condition, for things the builder can not express
+    /// the placeholders `$1`, `$2`,.. refers to the values in the order they are listed
+    /// and are renumbered to fit the rest of the query.
+    /// The sql is appended as is, never put user input in it.
+    pub fn filter_raw(&mut self, sql: &str, values: Vec<&ToValue>) -> &mut Self {
+        let mut params = vec![];
+        for v in values {
+            params.push(Operand::Value(v.to_db_type()));
+        }
+        let filter = Filter::bare_new(Operand::Raw(sql.to_string()),
+                                      Equality::RAW,
+                                      Operand::Vec(params));
+        self.add_filter(filter)
+    }
+
     /// column = value
     pub fn filter_eq(&mut self, column: &str, value: &ToValue) -> &mut Self {
         self.add_filter(Filter::new(column, Equality::EQ, value))
@@ -1013,12 +2114,37 @@ impl Query{
         self.add_value(operand)
     }
 
+    /// a row of a multi-row insert, ie: `VALUES ($1, $2), ($3, $4)`,
+    /// in the same order as the columns
+    pub fn add_value_row(&mut self, row: Vec<Operand>) -> &mut Self {
+        self.value_rows.push(row);
+        self
+    }
+
+    /// set the column to its default when inserting/updating records
+    pub fn set_default(&mut self, column: &str) -> &mut Self {
+        self.column(column);
+        self.add_value(Operand::Default)
+    }
+
     /// set a value of a column when inserting/updating records
     pub fn set(&mut self, column: &str, value: &ToValue) -> &mut Self {
         self.column(column);
         self.value(value)
     }
 
+    /// pair the column with the value to insert, the columns are written in the order they are added.
+    /// Inserting the same column again replaces its value,
+    /// excluding the column drops its value too
+    pub fn insert_value(&mut self, column: &str, value: &ToValue) -> &mut Self {
+        let operand = Operand::Value(value.to_db_type());
+        match self.column_values.iter().position(|&(ref c, _)| c == column) {
+            Some(index) => self.column_values[index].1 = operand,
+            None => self.column_values.push((checked_column(column), operand)),
+        }
+        self
+    }
+
     pub fn return_all(&mut self) -> &mut Self {
         self.enumerate_column_as_return("*")
     }
@@ -1030,6 +2156,13 @@ impl Query{
         self
     }
 
+    /// return an expression of the inserted or updated record,
+    /// ie: `RETURNING length(name) AS name_length`
+    pub fn return_field(&mut self, field: Field) -> &mut Self {
+        self.enumerated_returns.push(field);
+        self
+    }
+
     pub fn enumerate_column_as_return(&mut self, column: &str) -> &mut Self {
         let column_name = ColumnName::from_str(column);
         let operand = Operand::ColumnName(column_name);
@@ -1042,9 +2175,15 @@ impl Query{
     }
 
     /// build the query only, not executed, useful when debugging
-    pub fn build(&mut self, db: &Database) -> SqlFrag {
+    pub fn build(&mut self, db: &Database) -> Result<SqlFrag, DbError> {
+        self.build_for(db.sql_options())
+    }
+
+    /// build the sql in the dialect described by the sql options,
+    /// no database connection is needed
+    pub fn build_for(&mut self, sql_options: Vec<SqlOption>) -> Result<SqlFrag, DbError> {
         self.finalize();
-        db.build_query(self)
+        SqlBuilder::new(sql_options).build_query(self)
     }
 
     /// expects a return, such as select, insert/update with returning clause
@@ -1073,6 +2212,127 @@ impl Query{
         Ok(result.cast())
     }
 
+    /// execute the query, then convert each record to T only as the iterator is advanced
+    pub fn collect_iter<T: IsDao + IsTable>(&mut self,
+                                            db: &Database)
+                                            -> Result<CastIter<T>, DbError> {
+        let result = try!(self.retrieve(db));
+        Ok(result.cast_iter())
+    }
+
+    /// execute the query and get the value of the first selected field of the first record,
+    /// such as a count or the latest id, without defining a struct for it.
+    /// No records, a NULL value or a value of another type is an error
+    pub fn collect_scalar<T: ExactFromValue>(&mut self, db: &Database) -> Result<T, DbError> {
+        let dao = match try!(self.retrieve_one(db)) {
+            Some(dao) => dao,
+            None => return Err(DbError::new("No record for the scalar value")),
+        };
+        let column = match self.enumerated_fields.first() {
+            Some(&Field { name: Some(ref name), .. }) => Some(name.to_string()),
+            Some(&Field { operand: Operand::ColumnName(ref column), .. }) => {
+                Some(column.column.to_string())
+            }
+            _ => None,
+        };
+        let value = match column {
+            Some(ref column) if dao.values.contains_key(column) => dao.get_value(column),
+            // the database names an unnamed expression, ie: `count`
+            _ if dao.values.len() == 1 => dao.values.values().next().unwrap().clone(),
+            _ => return Err(DbError::new("Unable to tell the column of the scalar value")),
+        };
+        match value {
+            Value::Null => Err(DbError::new("The scalar value is NULL")),
+            value => T::from_value_exact(&value),
+        }
+    }
+
+    /// execute the query, then convert the records to T keyed by the value of the column
+    pub fn collect_map<K, T>(&mut self,
+                             key: &str,
+                             on_duplicate: DuplicateKey,
+                             db: &Database)
+                             -> Result<HashMap<K, T>, DbError>
+        where K: FromValue + Eq + Hash + fmt::Debug,
+              T: IsDao + IsTable
+    {
+        let result = try!(self.retrieve(db));
+        result.cast_map(key, on_duplicate)
+    }
+
+    /// this query without the ordering and paging, used as the source of counts
+    fn to_counted(&self) -> Query {
+        let mut query = self.clone();
+        query.order_by = vec![];
+        query.order_by_collations = BTreeMap::new();
+        query.page = None;
+        query.page_size = None;
+        query
+    }
+
+    /// the count value as returned by the different databases
+    fn count_value(value: Value) -> Result<i64, DbError> {
+        match value {
+            Value::I64(x) => Ok(x),
+            Value::I32(x) => Ok(x as i64),
+            Value::String(ref x) => {
+                match x.parse::<i64>() {
+                    Ok(x) => Ok(x),
+                    Err(e) => Err(DbError::from_string(format!("Invalid count {}", e))),
+                }
+            }
+            value => Err(DbError::from_string(format!("Unexpected count {:?}", value))),
+        }
+    }
+
+    /// count the records that would have been returned by this query, ignoring the paging
+    pub fn count(&self, db: &Database) -> Result<usize, DbError> {
+        let mut count = Query::select();
+        count.field(Function::new("COUNT", vec![Operand::Raw("*".to_string())]).AS("count"))
+             .from_query(self.to_counted(), "counted");
+        let dao = try!(count.retrieve_one(db));
+        match dao {
+            Some(dao) => Ok(try!(Self::count_value(dao.get_value("count"))) as usize),
+            None => Err(DbError::new("No result from count")),
+        }
+    }
+
+    /// count the records of this query for each value of the column,
+    /// ie: `SELECT category, COUNT(*) AS count FROM (..) AS counted GROUP BY category`
+    /// the pairs are ordered by the column value
+    pub fn count_by(&self, column: &str, db: &Database) -> Result<Vec<(Value, i64)>, DbError> {
+        let mut count = Query::select();
+        count.column(column)
+             .field(Function::new("COUNT", vec![Operand::Raw("*".to_string())]).AS("count"))
+             .from_query(self.to_counted(), "counted")
+             .group_by(vec![column])
+             .asc(column);
+        let result = try!(count.retrieve(db));
+        let mut counts = vec![];
+        for dao in result.dao {
+            let count = try!(Self::count_value(dao.get_value("count")));
+            counts.push((dao.get_value(column), count));
+        }
+        Ok(counts)
+    }
+
+    /// execute the query and count the total records, then convert the page of results
+    pub fn collect_page<T: IsDao + IsTable>(&mut self,
+                                            db: &Database)
+                                            -> Result<PagedResult<T>, DbError> {
+        let total = try!(self.count(db));
+        let mut result = try!(self.retrieve(db));
+        result.total = Some(total);
+        result.page = self.page;
+        result.page_size = self.page_size;
+        Ok(PagedResult {
+            data: result.cast(),
+            total: total,
+            page: result.page,
+            page_size: result.page_size,
+        })
+    }
+
     /// execute the query then collect only 1 record
     /// TODO: use Result<T,Error> instead of Option<T>
     pub fn collect_one<T: IsDao + IsTable>(&mut self, db: &Database) -> Result<T, DbError> {
@@ -1,4 +1,5 @@
 use std::fmt;
+use query::{Operand, ColumnName};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Foreign {
@@ -7,6 +8,138 @@ pub struct Foreign {
     pub column: String,
 }
 
+/// what happens to the referring rows when the referred row is deleted or updated
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferentialAction {
+    Cascade,
+    SetNull,
+    Restrict,
+    NoAction,
+}
+
+impl ReferentialAction{
+
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::SetNull => "SET NULL",
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::NoAction => "NO ACTION",
+        }
+    }
+}
+
+/// a foreign key constraint of a table, which can span multiple columns
+/// ie: FOREIGN KEY (order_id, product_id) REFERENCES order_line (order_id, product_id)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    /// the name of the constraint
+    pub name: Option<String>,
+    /// the columns of this table
+    pub columns: Vec<String>,
+    pub foreign_schema: String,
+    pub foreign_table: String,
+    /// the referred columns, in the same order as the columns
+    pub foreign_columns: Vec<String>,
+    /// ON DELETE action, the database default (NO ACTION) when None
+    pub on_delete: Option<ReferentialAction>,
+    /// ON UPDATE action, the database default (NO ACTION) when None
+    pub on_update: Option<ReferentialAction>,
+    /// the check is deferred until the end of the transaction (postgresql only)
+    pub deferrable: bool,
+}
+
+impl ForeignKey{
+
+    pub fn new(name: &str,
+               columns: Vec<&str>,
+               foreign_schema: &str,
+               foreign_table: &str,
+               foreign_columns: Vec<&str>)
+               -> Self {
+        assert!(columns.len() == foreign_columns.len(),
+                "Each column should refer to a foreign column");
+        ForeignKey {
+            name: Some(name.to_string()),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            foreign_schema: foreign_schema.to_string(),
+            foreign_table: foreign_table.to_string(),
+            foreign_columns: foreign_columns.iter().map(|c| c.to_string()).collect(),
+            on_delete: None,
+            on_update: None,
+            deferrable: false,
+        }
+    }
+
+    /// DEFERRABLE INITIALLY DEFERRED, ignored in databases that don't support it
+    pub fn deferrable(mut self) -> Self {
+        self.deferrable = true;
+        self
+    }
+
+    /// ON DELETE action
+    pub fn on_delete(mut self, action: ReferentialAction) -> Self {
+        self.on_delete = Some(action);
+        self
+    }
+
+    /// ON UPDATE action
+    pub fn on_update(mut self, action: ReferentialAction) -> Self {
+        self.on_update = Some(action);
+        self
+    }
+
+    /// the complete name of the referred table
+    pub fn foreign_complete_name(&self) -> String {
+        format!("{}.{}", self.foreign_schema, self.foreign_table)
+    }
+}
+
+/// an index on the columns or expressions of a table,
+/// ie: CREATE INDEX idx_product_active ON product (active) WHERE active = true
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub name: String,
+    /// the indexed columns or expressions, ie: `LOWER(email)`
+    pub expressions: Vec<Operand>,
+    pub unique: bool,
+    /// only the rows matching this sql condition are indexed (postgresql, sqlite)
+    pub predicate: Option<String>,
+}
+
+impl Index{
+
+    pub fn new(name: &str, columns: Vec<&str>) -> Self {
+        let expressions = columns.iter()
+                                 .map(|c| Operand::ColumnName(ColumnName::from_str(c)))
+                                 .collect();
+        Index::on_expressions(name, expressions)
+    }
+
+    /// index the values computed from the columns, ie: `LOWER(email)`
+    pub fn on_expressions(name: &str, expressions: Vec<Operand>) -> Self {
+        assert!(!expressions.is_empty(), "There should be columns to index");
+        Index {
+            name: name.to_string(),
+            expressions: expressions,
+            unique: false,
+            predicate: None,
+        }
+    }
+
+    /// UNIQUE index
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// a partial index, only the rows matching the condition are indexed, ie: `active = true`
+    pub fn predicate(mut self, predicate: &str) -> Self {
+        self.predicate = Some(predicate.to_string());
+        self
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Column {
     pub name: String,
@@ -22,23 +155,70 @@ pub struct Column {
     pub foreign: Option<Foreign>,
     ///determines if the column is inherited from the parent table
     pub is_inherited: bool,
+    /// the collation of a text column, ie: `en_US`, `utf8mb4_unicode_ci`
+    pub collation: Option<String>,
+    /// the value is generated by the database, ie: `GENERATED ALWAYS AS IDENTITY`, `AUTO_INCREMENT`
+    pub is_identity: bool,
+    /// computed from the other columns, ie: `GENERATED ALWAYS AS (price * qty) STORED`,
+    /// it is never written on insert or update
+    pub is_generated: bool,
 }
 
 impl Column{
 
     fn is_keyword(str: &str) -> bool {
-        let keyword = ["type", "yield", "macro"];
+        let keyword = ["abstract", "alignof", "as", "become", "box", "break", "const",
+                       "continue", "crate", "do", "else", "enum", "extern", "false", "final",
+                       "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod",
+                       "move", "mut", "offsetof", "override", "priv", "proc", "pub", "pure",
+                       "ref", "return", "self", "sizeof", "static", "struct", "super", "trait",
+                       "true", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+                       "while", "yield"];
         keyword.contains(&str)
     }
 
+    /// MixedCase column names are converted to snake_case, ie: OrderDate becomes order_date
+    fn snake_case(str: &str) -> String {
+        let mut snake = String::new();
+        let mut prev_lower = false;
+        for ch in str.chars() {
+            if ch.is_uppercase() {
+                if prev_lower {
+                    snake.push('_');
+                }
+                snake.extend(ch.to_lowercase());
+                prev_lower = false;
+            } else {
+                snake.push(ch);
+                prev_lower = ch.is_lowercase() || ch.is_digit(10);
+            }
+        }
+        snake
+    }
 
-    ///some column names may be a rust reserve keyword, so have to correct them
+    /// the column name is used as a rust field name, so it has to be snake_case
+    /// and some column names may be a rust reserve keyword, so have to correct them
+    /// the original name is still used when referring to the column in the database
     pub fn corrected_name(&self) -> String {
-        if Self::is_keyword(&self.name) {
+        let snake = Self::snake_case(&self.name);
+        if Self::is_keyword(&snake) {
             println!("Warning: {} is rust reserved keyword", self.name);
-            return format!("{}_", self.name);
+            return format!("{}_", snake);
         }
-        self.name.to_string()
+        snake
+    }
+
+    /// column names with upper case letters needs to be quoted,
+    /// since unquoted identifiers are folded to lower case in postgresql
+    pub fn needs_quote(&self) -> bool {
+        Self::name_needs_quote(&self.name)
+    }
+
+    /// a plain identifier with upper case letters,
+    /// expressions such as `COUNT(*)` are left as they are
+    pub fn name_needs_quote(name: &str) -> bool {
+        name.chars().any(|c| c.is_uppercase()) &&
+        name.chars().all(|c| c.is_alphanumeric() || c == '_')
     }
 
     pub fn displayname(&self) -> String {
@@ -186,6 +366,10 @@ pub struct Table {
 
     /// views can also be generated
     pub is_view: bool,
+
+    /// foreign keys which spans multiple columns,
+    /// single column foreign keys are in the `foreign` of the column
+    pub foreign_keys: Vec<ForeignKey>,
 }
 impl fmt::Display for Table {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -299,6 +483,20 @@ impl Table{
         None
     }
 
+    /// the pairs of columns which end up with the same rust field name,
+    /// ie: `orderDate` and `order_date` are both `order_date`
+    pub fn field_name_collisions(&self) -> Vec<(&Column, &Column)> {
+        let mut collisions = vec![];
+        for (i, column) in self.columns.iter().enumerate() {
+            for other in &self.columns[i + 1..] {
+                if column.corrected_name() == other.corrected_name() {
+                    collisions.push((column, other));
+                }
+            }
+        }
+        collisions
+    }
+
     /// return all the primary columns of this table
     pub fn primary_columns(&self) -> Vec<&Column> {
         let mut primary_columns = Vec::new();
@@ -361,6 +559,11 @@ impl Table{
         false
     }
 
+    /// whether the column is computed by the database, and can't be written
+    pub fn is_generated(&self, column_name: &str) -> bool {
+        self.columns.iter().any(|c| c.name == column_name && c.is_generated)
+    }
+
     /// return all the unique keys of this table
     pub fn unique_columns(&self) -> Vec<&Column> {
         let mut unique_columns = Vec::new();
@@ -416,14 +619,18 @@ impl Table{
     /// get all other tables that is refering to this table
     /// when any column of a table refers to this table
     /// get_has_many
+    /// tables with the same name in other schemas are not referring to this table
     pub fn referring_tables<'a>(&self, tables: &'a Vec<Table>) -> Vec<(&'a Table, &'a Column)> {
         let mut referring = Vec::new();
         for t in tables {
             for c in &t.columns {
-                if c.foreign.is_some() {
-                    if &self.name == &c.foreign.clone().unwrap().table {
-                        referring.push((t, c));
+                match c.foreign {
+                    Some(ref foreign) => {
+                        if self.name == foreign.table && self.schema == foreign.schema {
+                            referring.push((t, c));
+                        }
                     }
+                    None => (),
                 }
             }
         }
@@ -695,3 +902,104 @@ fn capitalize(str: &str) -> String {
 fn test_capitalize() {
     assert_eq!(capitalize("hello"), "Hello".to_string());
 }
+
+#[cfg(test)]
+fn column_named(name: &str) -> Column {
+    Column {
+        name: name.to_string(),
+        data_type: "String".to_string(),
+        db_data_type: "character varying".to_string(),
+        is_primary: false,
+        is_unique: false,
+        default: None,
+        comment: None,
+        not_null: false,
+        foreign: None,
+        is_inherited: false,
+        collation: None,
+        is_identity: false,
+        is_generated: false,
+    }
+}
+
+#[test]
+fn test_keyword_column_name() {
+    let column = column_named("type");
+    assert_eq!(column.corrected_name(), "type_".to_string());
+    assert!(!column.needs_quote());
+    assert_eq!(column_named("match").corrected_name(), "match_".to_string());
+}
+
+#[test]
+fn test_mixed_case_column_name() {
+    let column = column_named("OrderDate");
+    assert_eq!(column.corrected_name(), "order_date".to_string());
+    assert!(column.needs_quote());
+    assert_eq!(column.name, "OrderDate".to_string());
+    assert_eq!(column_named("productID").corrected_name(), "product_id".to_string());
+    assert!(!Column::name_needs_quote("COUNT(*)"));
+}
+
+#[test]
+fn test_field_name_collisions() {
+    let table = table_named("bazaar",
+                            "orders",
+                            vec![column_named("orderDate"),
+                                 column_named("order_date"),
+                                 column_named("name")]);
+    let collisions = table.field_name_collisions();
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].0.name, "orderDate".to_string());
+    assert_eq!(collisions[0].1.name, "order_date".to_string());
+}
+
+#[cfg(test)]
+fn table_named(schema: &str, name: &str, columns: Vec<Column>) -> Table {
+    Table {
+        schema: schema.to_string(),
+        name: name.to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: columns,
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+#[test]
+fn test_reverse_has_many() {
+    let mut user_id = column_named("user_id");
+    user_id.is_primary = true;
+    let mut review_id = column_named("review_id");
+    review_id.is_primary = true;
+    let mut review_user_id = column_named("user_id");
+    review_user_id.foreign = Some(Foreign {
+        schema: "bazaar".to_string(),
+        table: "users".to_string(),
+        column: "user_id".to_string(),
+    });
+    let mut other_user_id = column_named("user_id");
+    other_user_id.foreign = Some(Foreign {
+        schema: "archive".to_string(),
+        table: "users".to_string(),
+        column: "user_id".to_string(),
+    });
+    let tables = vec![
+        table_named("bazaar", "users", vec![user_id]),
+        table_named("bazaar", "user_review",
+                    vec![review_id, review_user_id, column_named("comment")]),
+        table_named("archive", "user_review_log",
+                    vec![column_named("log_id"), other_user_id, column_named("comment")]),
+    ];
+    let users = &tables[0];
+
+    let has_many: Vec<RefTable> = users.get_all_applicable_reference(&tables)
+                                       .into_iter()
+                                       .filter(|r| r.is_has_many)
+                                       .collect();
+    assert_eq!(has_many.len(), 1);
+    assert_eq!(has_many[0].table.name, "user_review".to_string());
+    assert!(has_many[0].is_direct);
+    assert_eq!(has_many[0].member_name(users), "user_review".to_string());
+}
@@ -1,13 +1,29 @@
 use dao::Value;
 use database::SqlOption;
+use table::Column;
 use std::fmt;
 
+/// the case of the sql keywords when written
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum KeywordCase {
+    /// SELECT, FROM, WHERE (default)
+    Upper,
+    /// select, from, where
+    Lower,
+}
+
 /// sql fragment
 /// use this for writing SQL statements
 pub struct SqlFrag {
     pub sql: String,
     pub params: Vec<Value>,
     pub sql_options: Vec<SqlOption>,
+    pub keyword_case: KeywordCase,
+    /// reuse the placeholder of an identical value that is already a parameter,
+    /// only applicable to numbered parameters
+    pub reuse_params: bool,
 }
 
 impl fmt::Display for SqlFrag{
@@ -35,15 +51,50 @@ impl SqlFrag{
             sql: String::new(),
             params: vec![],
             sql_options: sql_options,
+            keyword_case: KeywordCase::Upper,
+            reuse_params: false,
         }
     }
 
+    /// take the sql and the ordered parameters,
+    /// useful when executing the built sql with some other driver
+    pub fn into_parts(self) -> (String, Vec<Value>) {
+        (self.sql, self.params)
+    }
+
     #[inline]
     pub fn append(&mut self, str: &str) -> &mut Self {
         self.sql.push_str(str);
         self
     }
 
+    /// append a column name, ie: `name` or `product.name`, the parts with upper case letters
+    /// are quoted since they would be folded to lower case, see `Column::needs_quote`
+    pub fn column_name(&mut self, name: &str) -> &mut Self {
+        let parts: Vec<String> = name.split('.')
+                                     .map(|part| {
+                                         if !Column::name_needs_quote(part) {
+                                             part.to_string()
+                                         } else if self.sql_options
+                                                       .contains(&SqlOption::UsesBacktickQuote) {
+                                             format!("`{}`", part)
+                                         } else {
+                                             format!("\"{}\"", part)
+                                         }
+                                     })
+                                     .collect();
+        self.append(&parts.join("."))
+    }
+
+    /// append an sql keyword in the configured keyword case
+    #[inline]
+    pub fn keyword(&mut self, keyword: &str) -> &mut Self {
+        match self.keyword_case {
+            KeywordCase::Upper => self.append(&keyword.to_uppercase()),
+            KeywordCase::Lower => self.append(&keyword.to_lowercase()),
+        }
+    }
+
     #[inline]
     pub fn appendln(&mut self, str: &str) -> &mut Self {
         self.append(str);
@@ -83,6 +134,14 @@ impl SqlFrag{
     pub fn sp(&mut self) -> &mut Self {
         self.append(" ")
     }
+    /// add a space, unless the sql already ends with one
+    #[inline]
+    pub fn sp_once(&mut self) -> &mut Self {
+        if !self.sql.ends_with(" ") {
+            self.sp();
+        }
+        self
+    }
     #[inline]
     pub fn spaces(&mut self, n: i32) -> &mut Self {
         for _ in 0..n {
@@ -101,7 +160,7 @@ impl SqlFrag{
         if diff > 0 {
             self.spaces(diff);
         }
-        self.append(trim);
+        self.keyword(trim);
         self.sp()
     }
     /// write the string, aligning to the left side of the middle space (river)
@@ -115,7 +174,7 @@ impl SqlFrag{
     pub fn right_river(&mut self, str: &str) -> &mut Self {
         self.ln();
         self.river("");
-        self.append(str)
+        self.keyword(str)
     }
 
     #[inline]
@@ -123,13 +182,97 @@ impl SqlFrag{
         self.comma().sp()
     }
 
+    /// write the string as a quoted sql literal, for places where a parameter is not allowed
+    pub fn string_literal(&mut self, str: &str) -> &mut Self {
+        let quoted = quote(str);
+        self.append(&quoted)
+    }
+
     #[inline]
     pub fn comment(&mut self, comment: &str) -> &mut Self {
         self.append("-- ");
         self.append(comment)
     }
+    /// the sql with the parameters rendered in place of the placeholders,
+    /// only use this for logging and debugging, never for executing
+    pub fn to_string_interpolated(&self) -> String {
+        let mut sql = String::new();
+        let mut chars = self.sql.chars().peekable();
+        let mut question_mark_index = 0;
+        while let Some(ch) = chars.next() {
+            if ch == '$' && self.sql_options.contains(&SqlOption::UsesNumberedParam) {
+                let mut number = String::new();
+                while let Some(&digit) = chars.peek() {
+                    if digit.is_digit(10) {
+                        number.push(digit);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                match number.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= self.params.len() => {
+                        sql.push_str(&interpolate_value(&self.params[n - 1]));
+                    }
+                    _ => {
+                        sql.push(ch);
+                        sql.push_str(&number);
+                    }
+                }
+            } else if ch == '?' && self.sql_options.contains(&SqlOption::UsesQuestionMark) &&
+                      question_mark_index < self.params.len() {
+                sql.push_str(&interpolate_value(&self.params[question_mark_index]));
+                question_mark_index += 1;
+            } else {
+                sql.push(ch);
+            }
+        }
+        sql
+    }
+
+    /// append a raw sql, binding the values in place of its `$1`, `$2`,.. placeholders
+    pub fn append_raw(&mut self, sql: &str, values: &Vec<Value>) -> &mut Self {
+        let mut chars = sql.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch == '$' {
+                let mut number = String::new();
+                while let Some(&digit) = chars.peek() {
+                    if digit.is_digit(10) {
+                        number.push(digit);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                match number.parse::<usize>() {
+                    Ok(n) => {
+                        assert!(n >= 1 && n <= values.len(),
+                                "There is no value for the placeholder ${}", n);
+                        self.parameter(values[n - 1].clone());
+                    }
+                    Err(_) => {
+                        self.sql.push(ch);
+                    }
+                }
+            } else {
+                self.sql.push(ch);
+            }
+        }
+        self
+    }
+
     ///append parameter including the needed sql keywords
     pub fn parameter(&mut self, param: Value) {
+        if self.reuse_params && self.sql_options.contains(&SqlOption::UsesNumberedParam) {
+            match self.params.iter().position(|p| p == &param) {
+                Some(index) => {
+                    let numbered_param = format!("${} ", index + 1);
+                    self.append(&numbered_param);
+                    return;
+                }
+                None => (),
+            }
+        }
         self.params.push(param);
         if self.sql_options.contains(&SqlOption::UsesNumberedParam) {
             let numbered_param = format!("${} ", self.params.len());
@@ -140,3 +283,74 @@ impl SqlFrag{
     }
 
 }
+
+/// a debug rendering of the value as an sql literal
+fn interpolate_value(value: &Value) -> String {
+    match *value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(ref x) => format!("{}", x).to_uppercase(),
+        Value::I8(ref x) => format!("{}", x),
+        Value::I16(ref x) => format!("{}", x),
+        Value::I32(ref x) => format!("{}", x),
+        Value::I64(ref x) => format!("{}", x),
+        Value::U8(ref x) => format!("{}", x),
+        Value::U16(ref x) => format!("{}", x),
+        Value::U32(ref x) => format!("{}", x),
+        Value::U64(ref x) => format!("{}", x),
+        Value::F32(ref x) => format!("{}", x),
+        Value::F64(ref x) => format!("{}", x),
+        Value::String(ref x) => quote(x),
+        Value::Uuid(ref x) => quote(&format!("{}", x)),
+        Value::DateTime(ref x) => quote(&x.to_rfc3339()),
+        Value::DateTimeOffset(ref x) => quote(&x.to_rfc3339()),
+        Value::NaiveDate(ref x) => quote(&format!("{}", x)),
+        Value::NaiveTime(ref x) => quote(&format!("{}", x)),
+        Value::NaiveDateTime(ref x) => quote(&format!("{}", x)),
+        Value::VecU8(ref x) => quote(&format!("{:?}", x)),
+        Value::Object(ref x) => quote(&format!("{:?}", x)),
+        Value::Json(ref x) => quote(&format!("{}", x)),
+        Value::Array(ref x) => {
+            let elements: Vec<String> = x.to_values().iter().map(interpolate_value).collect();
+            format!("ARRAY[{}]", elements.join(", "))
+        }
+        Value::Money(ref x) => quote(&x.to_decimal_string()),
+    }
+}
+
+/// single quote the string, escaping the quotes inside it
+fn quote(str: &str) -> String {
+    format!("'{}'", str.replace("'", "''"))
+}
+
+#[test]
+fn test_interpolated_sql() {
+    let mut w = SqlFrag::new(vec![SqlOption::UsesNumberedParam]);
+    w.append("UPDATE product SET name = ");
+    w.parameter(Value::String("Nvidia's GTX660".to_string()));
+    w.append(", stocks = ");
+    w.parameter(Value::I32(10));
+    w.append(", description = ");
+    w.parameter(Value::Null);
+    assert_eq!(w.to_string_interpolated(),
+               "UPDATE product SET name = 'Nvidia''s GTX660' , stocks = 10 , description = NULL ");
+}
+
+#[test]
+fn test_mixed_case_column_name() {
+    let mut w = SqlFrag::new(vec![SqlOption::UsesNumberedParam]);
+    w.column_name("product.OrderDate");
+    w.append(", ");
+    w.column_name("name");
+    assert_eq!(w.sql, "product.\"OrderDate\", name");
+}
+
+#[test]
+fn test_interpolated_question_mark() {
+    let mut w = SqlFrag::new(vec![SqlOption::UsesQuestionMark]);
+    w.append("SELECT * FROM product WHERE name = ");
+    w.parameter(Value::String("GTX660".to_string()));
+    w.append(" AND stocks > ");
+    w.parameter(Value::I64(1));
+    assert_eq!(w.to_string_interpolated(),
+               "SELECT * FROM product WHERE name = 'GTX660' AND stocks > 1");
+}
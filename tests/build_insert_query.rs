@@ -50,7 +50,7 @@ fn test_insert_query() {
          .set("name", &"product1")
          .returns(vec!["category.name"]);
 
-    let frag = query.build(db.as_ref());
+    let frag = query.build(db.as_ref()).unwrap();
 
     let expected = "
    INSERT INTO bazaar.product( name ) 
@@ -0,0 +1,15 @@
+extern crate rustorm;
+
+use std::marker::PhantomData;
+use rustorm::query::{Query, TypedColumn, Equality};
+
+const NAME: TypedColumn<String> = TypedColumn {
+    name: "product.name",
+    column_type: PhantomData,
+};
+
+fn main() {
+    let mut query = Query::select_all();
+    // the value of a text column should be a String
+    query.filter_column(&NAME, Equality::EQ, &10);
+}
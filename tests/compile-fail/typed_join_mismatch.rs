@@ -0,0 +1,20 @@
+extern crate rustorm;
+extern crate uuid;
+
+use std::marker::PhantomData;
+use rustorm::query::TypedColumn;
+use uuid::Uuid;
+
+const PRODUCT_ID: TypedColumn<Uuid> = TypedColumn {
+    name: "product.product_id",
+    column_type: PhantomData,
+};
+const NAME: TypedColumn<String> = TypedColumn {
+    name: "product.name",
+    column_type: PhantomData,
+};
+
+fn main() {
+    // a uuid column can't be joined on a text column
+    PRODUCT_ID.eq(NAME);
+}
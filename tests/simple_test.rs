@@ -59,6 +59,7 @@ impl IsTable for Product{
             comment: None,
             columns: vec![],
             is_view: false,
+            foreign_keys: vec![],
         }
     }
 }
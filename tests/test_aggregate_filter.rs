@@ -0,0 +1,111 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::{Filter, Function, Operand, Equality, SUM, STRING_AGG};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+fn filtered_count() -> Query {
+    let mut query = Query::select();
+    query.field(Function::new("COUNT", vec![Operand::Raw("*".to_string())])
+                    .filter_where(Filter::new("active", Equality::EQ, &true))
+                    .AS("active_count"))
+         .from_table("product");
+    query
+}
+
+#[test]
+fn test_filtered_count_postgres() {
+    let db = Postgres::new();
+    let frag = filtered_count().build(&db).unwrap();
+
+    let expected = "
+   SELECT COUNT(*) FILTER (WHERE active = $1 ) AS active_count
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_filtered_count_mysql() {
+    let db = Mysql::new();
+    let frag = filtered_count().build(&db).unwrap();
+
+    let expected = "
+   SELECT COUNT(CASE WHEN active = ? THEN 1 END) AS active_count
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_filtered_count_sqlite() {
+    let db = Sqlite::new();
+    let frag = filtered_count().build(&db).unwrap();
+
+    let expected = "
+   SELECT COUNT(CASE WHEN active = $1 THEN 1 END) AS active_count
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_filtered_sum_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::select();
+    query.field(SUM("price")
+                    .filter_where(Filter::new("active", Equality::EQ, &true))
+                    .AS("active_total"))
+         .from_table("product");
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT SUM(CASE WHEN active = ? THEN price END) AS active_total
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+fn filtered_names() -> Query {
+    let mut query = Query::select();
+    query.field(STRING_AGG("name", ", ")
+                    .filter_where(Filter::new("active", Equality::EQ, &true))
+                    .AS("active_names"))
+         .from_table("product");
+    query
+}
+
+#[test]
+fn test_filtered_string_agg_postgres() {
+    let db = Postgres::new();
+    let frag = filtered_names().build(&db).unwrap();
+
+    let expected = "
+   SELECT STRING_AGG(name, $1 ) FILTER (WHERE active = $2 ) AS active_names
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_filtered_string_agg_mysql() {
+    let db = Mysql::new();
+    let frag = filtered_names().build(&db).unwrap();
+
+    let expected = "
+   SELECT GROUP_CONCAT(CASE WHEN active = ? THEN name END SEPARATOR ', ') AS active_names
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
@@ -0,0 +1,81 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+use rustorm::dao::{Value, Array};
+
+
+#[test]
+fn test_filter_any_array() {
+    let db = Postgres::new();
+    let ids = vec![1, 2, 3];
+
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_any_array("product_id", &ids);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+    WHERE product_id = ANY($1 )".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params, vec![Value::Array(Array::I32(vec![1, 2, 3]))]);
+}
+
+#[test]
+fn test_filter_any_empty_array() {
+    let db = Postgres::new();
+    let ids: Vec<i32> = vec![];
+
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_any_array("product_id", &ids);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+    WHERE 1 = 0".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert!(frag.params.is_empty());
+}
+
+#[test]
+fn test_display_array() {
+    let value = Value::Array(Array::I32(vec![1, 2]));
+    assert_eq!(format!("{}", value), "'[I32(1), I32(2)]'");
+}
+
+#[test]
+fn test_array_from_values() {
+    let array = Array::from_values(vec![Value::String("a".to_string()),
+                                        Value::String("b".to_string())]);
+    assert_eq!(array, Array::String(vec!["a".to_string(), "b".to_string()]));
+    assert_eq!(array.len(), 2);
+}
+
+#[test]
+#[should_panic]
+fn test_array_mixed_values() {
+    Array::from_values(vec![Value::I32(1), Value::String("b".to_string())]);
+}
+
+#[test]
+fn test_any_array_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_any_array("product_id", &vec![1, 2, 3]);
+    assert!(query.build(&db).is_err());
+}
@@ -0,0 +1,94 @@
+extern crate rustorm;
+
+use rustorm::dao::Value;
+use rustorm::database::Database;
+use rustorm::table::{Table, Column};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+fn column(name: &str, db_data_type: &str, is_primary: bool) -> Column {
+    Column {
+        name: name.to_string(),
+        data_type: "".to_string(),
+        db_data_type: db_data_type.to_string(),
+        is_primary: is_primary,
+        is_unique: false,
+        default: None,
+        comment: None,
+        not_null: is_primary,
+        foreign: None,
+        is_inherited: false,
+        collation: None,
+        is_identity: false,
+        is_generated: false,
+    }
+}
+
+fn stock() -> Table {
+    Table {
+        schema: "bazaar".to_string(),
+        name: "stock".to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![column("stock_id", "integer", true),
+                      column("quantity", "integer", false),
+                      column("location", "character varying", false)],
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+fn rows() -> Vec<Vec<Value>> {
+    vec![vec![Value::I32(1), Value::I32(10), Value::String("A1".to_string())],
+         vec![Value::I32(2), Value::I32(20), Value::String("B2".to_string())],
+         vec![Value::I32(3), Value::I32(30), Value::String("C3".to_string())]]
+}
+
+#[test]
+fn test_bulk_update_from_values() {
+    let db = Postgres::new();
+    let frag = db.sql_builder()
+                 .build_bulk_update(&stock(), "stock_id", vec!["quantity", "location"], &rows())
+                 .unwrap();
+
+    let expected = "
+   UPDATE bazaar.stock
+      SET quantity = v.quantity, location = v.location
+     FROM (VALUES \
+(CAST($1 AS integer), CAST($2 AS integer), CAST($3 AS character varying)), \
+(CAST($4 AS integer), CAST($5 AS integer), CAST($6 AS character varying)), \
+(CAST($7 AS integer), CAST($8 AS integer), CAST($9 AS character varying))) \
+AS v(stock_id, quantity, location)
+    WHERE stock.stock_id = v.stock_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 9);
+    assert_eq!(frag.params[3], Value::I32(2));
+}
+
+#[test]
+fn test_bulk_update_case_fallback() {
+    let db = Mysql::new();
+    let frag = db.sql_builder()
+                 .build_bulk_update(&stock(), "stock_id", vec!["quantity", "location"], &rows())
+                 .unwrap();
+
+    let expected = "
+   UPDATE stock
+      SET quantity = CASE stock_id WHEN ? THEN ? WHEN ? THEN ? WHEN ? THEN ? END, \
+location = CASE stock_id WHEN ? THEN ? WHEN ? THEN ? WHEN ? THEN ? END
+    WHERE stock_id IN (?, ?, ?)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params,
+               vec![Value::I32(1), Value::I32(10), Value::I32(2), Value::I32(20),
+                    Value::I32(3), Value::I32(30),
+                    Value::I32(1), Value::String("A1".to_string()),
+                    Value::I32(2), Value::String("B2".to_string()),
+                    Value::I32(3), Value::String("C3".to_string()),
+                    Value::I32(1), Value::I32(2), Value::I32(3)]);
+}
@@ -0,0 +1,33 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::{Case, Condition, Equality, Function, Operand};
+use rustorm::dao::{ToValue, Value};
+use rustorm::platform::postgres::Postgres;
+
+
+#[test]
+fn test_case_in_sum() {
+    let db = Postgres::new();
+    let active = Case::new()
+                     .when(Condition::new("status", Equality::EQ, &"a"),
+                           Operand::Value(1.to_db_type()))
+                     .otherwise(Operand::Value(0.to_db_type()));
+    let mut query = Query::select();
+    query.column("category_id")
+         .field(Function::new("SUM", vec![Operand::Case(active)]).AS("active_count"))
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, SUM(CASE WHEN status = $1 THEN $2 ELSE $3 END) AS active_count
+     FROM bazaar.product
+ GROUP BY category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params,
+               vec![Value::String("a".to_string()), Value::I32(1), Value::I32(0)]);
+}
@@ -0,0 +1,84 @@
+extern crate rustorm;
+
+use rustorm::database::Database;
+use rustorm::table::{Table, Column};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+fn name() -> Column {
+    Column {
+        name: "name".to_string(),
+        data_type: "String".to_string(),
+        db_data_type: "text".to_string(),
+        is_primary: false,
+        is_unique: false,
+        default: None,
+        comment: None,
+        not_null: true,
+        foreign: None,
+        is_inherited: false,
+        collation: None,
+        is_identity: false,
+        is_generated: false,
+    }
+}
+
+fn product() -> Table {
+    Table {
+        schema: "bazaar".to_string(),
+        name: "product".to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![name()],
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+#[test]
+fn test_comment_on_table() {
+    let db = Postgres::new();
+    let frag = db.sql_builder()
+                 .build_comment_on_table(&product(), "The product's details")
+                 .unwrap();
+    assert_eq!(frag.sql, "COMMENT ON TABLE bazaar.product IS 'The product''s details'");
+}
+
+#[test]
+fn test_comment_on_column() {
+    let db = Postgres::new();
+    let frag = db.sql_builder()
+                 .build_comment_on_column(&product(), &name(), "The product's name")
+                 .unwrap();
+    assert_eq!(frag.sql, "COMMENT ON COLUMN bazaar.product.name IS 'The product''s name'");
+}
+
+#[test]
+fn test_comment_on_table_mysql() {
+    let db = Mysql::new();
+    let frag = db.sql_builder()
+                 .build_comment_on_table(&product(), "The product's details")
+                 .unwrap();
+    assert_eq!(frag.sql, "ALTER TABLE product COMMENT = 'The product''s details'");
+}
+
+#[test]
+fn test_comment_on_column_mysql() {
+    let db = Mysql::new();
+    let frag = db.sql_builder()
+                 .build_comment_on_column(&product(), &name(), "C:\\ product's name")
+                 .unwrap();
+    assert_eq!(frag.sql,
+               "ALTER TABLE product MODIFY COLUMN name text NOT NULL COMMENT 'C:\\\\ product''s name'");
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_comment_on_table_sqlite() {
+    let db = Sqlite::new();
+    assert!(db.sql_builder().build_comment_on_table(&product(), "The product's details").is_err());
+}
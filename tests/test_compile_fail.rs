@@ -0,0 +1,10 @@
+extern crate trybuild;
+
+
+/// the typed columns reject values and columns of another type at compile time,
+/// the expected errors are in the .stderr file next to each case
+#[test]
+fn test_typed_column_mismatch() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile-fail/*.rs");
+}
@@ -61,10 +61,10 @@ fn test_complex_query() {
          .filter("product.name", Equality::EQ, &"GTX660 Ti videocard")
          .filter("category.name", Equality::EQ, &"Electronic")
          .group_by(vec!["category.name"])
-         .having("count(*)", Equality::GT, &1)
+         .having_raw("count(*)", Equality::GT, &1)
          .asc("product.name")
          .desc("product.created");
-    let frag = query.build(db.as_ref());
+    let frag = query.build(db.as_ref()).unwrap();
 
     let expected = "
    SELECT *
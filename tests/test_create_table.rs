@@ -0,0 +1,135 @@
+extern crate rustorm;
+
+use rustorm::database::DatabaseDDL;
+use rustorm::table::{Table, Column};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+fn column(name: &str, data_type: &str, db_data_type: &str, is_primary: bool) -> Column {
+    Column {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        db_data_type: db_data_type.to_string(),
+        is_primary: is_primary,
+        is_unique: false,
+        default: None,
+        comment: None,
+        not_null: is_primary,
+        foreign: None,
+        is_inherited: false,
+        collation: None,
+        is_identity: false,
+        is_generated: false,
+    }
+}
+
+fn product(collation: &str) -> Table {
+    let mut name = column("name", "String", "character varying", false);
+    name.collation = Some(collation.to_string());
+    Table {
+        schema: "bazaar".to_string(),
+        name: "product".to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![column("product_id", "i32", "integer", true), name],
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+fn orders() -> Table {
+    let mut order_id = column("order_id", "i32", "integer", true);
+    order_id.is_identity = true;
+    Table {
+        schema: "bazaar".to_string(),
+        name: "orders".to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![order_id, column("quantity", "i32", "integer", false)],
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+#[test]
+fn test_collated_column() {
+    let db = Postgres::new();
+    let frag = db.build_create_table(&product("en_US"));
+
+    let expected = "CREATE TABLE bazaar.product(\n    \
+product_id integer PRIMARY KEY , \n    \
+name character varying COLLATE \"en_US\"\n)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_collated_column_mysql() {
+    let db = Mysql::new();
+    let frag = db.build_create_table(&product("utf8mb4_unicode_ci"));
+
+    let expected = "CREATE TABLE product(\n    \
+product_id integer PRIMARY KEY , name text COLLATE utf8mb4_unicode_ci)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_collated_column_sqlite() {
+    let db = Sqlite::new();
+    let frag = db.build_create_table(&product("NOCASE"));
+
+    let expected = "CREATE TABLE product(\n    \
+product_id integer PRIMARY KEY , \n    \
+name text COLLATE \"NOCASE\"\n)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_identity_column() {
+    let db = Postgres::new();
+    let frag = db.build_create_table(&orders());
+
+    let expected = "CREATE TABLE bazaar.orders(\n    \
+order_id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY , \n    \
+quantity integer\n)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_identity_column_mysql() {
+    let db = Mysql::new();
+    let frag = db.build_create_table(&orders());
+
+    let expected = "CREATE TABLE orders(\n    \
+order_id integer AUTO_INCREMENT PRIMARY KEY , quantity integer)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_identity_column_sqlite() {
+    let db = Sqlite::new();
+    let frag = db.build_create_table(&orders());
+
+    let expected = "CREATE TABLE orders(\n    \
+order_id INTEGER PRIMARY KEY AUTOINCREMENT, \n    \
+quantity integer\n)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
@@ -0,0 +1,47 @@
+extern crate rustorm;
+
+use std::time::Duration;
+
+use rustorm::query::Query;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_last_30_days() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+
+    query.from_table("bazaar.orders")
+         .filter_since("created", Duration::from_secs(30 * 24 * 60 * 60));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.orders
+    WHERE created >= NOW() - INTERVAL '2592000 seconds'".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert!(frag.params.is_empty());
+}
+
+#[test]
+fn test_mysql_last_hour() {
+    let db = Mysql::new();
+    let mut query = Query::select_all();
+
+    query.from_table("bazaar.orders")
+         .filter_since("created", Duration::from_secs(60 * 60));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM orders
+    WHERE created >= NOW() - INTERVAL 3600 SECOND".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
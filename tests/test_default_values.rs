@@ -0,0 +1,103 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+#[test]
+fn test_default_values_postgres() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .default_values()
+         .return_all();
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.category
+  DEFAULT VALUES 
+RETURNING *".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_default_values_sqlite() {
+    let db = Sqlite::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .default_values();
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO category
+  DEFAULT VALUES".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_default_values_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .default_values();
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO category
+   VALUES ()".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_default_in_values() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.product")
+         .set("name", &"product1")
+         .set_default("product_id")
+         .set("price", &100);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.product( name, product_id, price ) 
+   VALUES ($1 , DEFAULT, $2 )".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 2);
+}
+
+#[test]
+fn test_update_set_default() {
+    let db = Postgres::new();
+    let mut query = Query::update();
+    query.from_table("bazaar.product")
+         .set("name", &"product1")
+         .set_default("price")
+         .filter_eq("name", &"product0");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   UPDATE bazaar.product
+      SET name = $1 , price = DEFAULT
+    WHERE name = $2".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 2);
+}
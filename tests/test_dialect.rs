@@ -0,0 +1,20 @@
+extern crate rustorm;
+
+use rustorm::database::{Database, Dialect};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+#[test]
+fn test_dialect() {
+    assert_eq!(Postgres::new().dialect(), Dialect::Postgres);
+    assert_eq!(Mysql::new().dialect(), Dialect::Mysql);
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_dialect_sqlite() {
+    assert_eq!(Sqlite::new().dialect(), Dialect::Sqlite);
+}
@@ -0,0 +1,86 @@
+extern crate rustorm;
+
+use rustorm::query::{Query, Equality};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+fn distinct_from(equality: Equality) -> Query {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .filter("category_id", equality, &1);
+    query
+}
+
+#[test]
+fn test_is_distinct_from() {
+    let db = Postgres::new();
+    let frag = distinct_from(Equality::IS_DISTINCT_FROM).build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE category_id IS DISTINCT FROM $1 ".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_is_not_distinct_from() {
+    let db = Postgres::new();
+    let frag = distinct_from(Equality::IS_NOT_DISTINCT_FROM).build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE category_id IS NOT DISTINCT FROM $1 ".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_is_distinct_from_mysql() {
+    let db = Mysql::new();
+    let frag = distinct_from(Equality::IS_DISTINCT_FROM).build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM product
+    WHERE NOT (category_id <=> ?)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_is_not_distinct_from_mysql() {
+    let db = Mysql::new();
+    let frag = distinct_from(Equality::IS_NOT_DISTINCT_FROM).build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM product
+    WHERE category_id <=> ?".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_is_distinct_from_sqlite() {
+    let db = Sqlite::new();
+    let frag = distinct_from(Equality::IS_DISTINCT_FROM).build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM product
+    WHERE category_id IS DISTINCT FROM $1 ".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
@@ -0,0 +1,134 @@
+extern crate rustorm;
+
+use rustorm::table::{Table, ForeignKey, ReferentialAction};
+use rustorm::database::Database;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+fn order_line() -> Table {
+    Table {
+        schema: "bazaar".to_string(),
+        name: "order_line".to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![],
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+#[test]
+fn test_composite_foreign_key() {
+    let db = Postgres::new();
+    let foreign_key = ForeignKey::new("fk_order_line_variant",
+                                      vec!["product_id", "variant_id"],
+                                      "bazaar",
+                                      "product_variant",
+                                      vec!["product_id", "variant_id"]);
+
+    let frag = db.sql_builder().build_add_foreign_key(&order_line(), &foreign_key);
+
+    let expected = "ALTER TABLE bazaar.order_line ADD CONSTRAINT fk_order_line_variant \
+                    FOREIGN KEY (product_id, variant_id) \
+                    REFERENCES bazaar.product_variant (product_id, variant_id)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_on_delete_cascade() {
+    let db = Postgres::new();
+    let foreign_key = ForeignKey::new("fk_order_line_order",
+                                      vec!["order_id"],
+                                      "bazaar",
+                                      "orders",
+                                      vec!["order_id"])
+                          .on_delete(ReferentialAction::Cascade);
+
+    let frag = db.sql_builder().build_add_foreign_key(&order_line(), &foreign_key);
+
+    let expected = "ALTER TABLE bazaar.order_line ADD CONSTRAINT fk_order_line_order \
+                    FOREIGN KEY (order_id) REFERENCES bazaar.orders (order_id) \
+                    ON DELETE CASCADE";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_on_delete_set_null() {
+    let db = Postgres::new();
+    let foreign_key = ForeignKey::new("fk_order_line_product",
+                                      vec!["product_id"],
+                                      "bazaar",
+                                      "product",
+                                      vec!["product_id"])
+                          .on_delete(ReferentialAction::SetNull)
+                          .on_update(ReferentialAction::Restrict);
+
+    let frag = db.sql_builder().build_add_foreign_key(&order_line(), &foreign_key);
+
+    let expected = "ALTER TABLE bazaar.order_line ADD CONSTRAINT fk_order_line_product \
+                    FOREIGN KEY (product_id) REFERENCES bazaar.product (product_id) \
+                    ON DELETE SET NULL ON UPDATE RESTRICT";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_deferrable_foreign_key() {
+    let db = Postgres::new();
+    let foreign_key = ForeignKey::new("fk_users_default_address",
+                                      vec!["address_id"],
+                                      "bazaar",
+                                      "address",
+                                      vec!["address_id"])
+                          .deferrable();
+    let users = Table { name: "users".to_string(), ..order_line() };
+
+    let frag = db.sql_builder().build_add_foreign_key(&users, &foreign_key);
+
+    let expected = "ALTER TABLE bazaar.users ADD CONSTRAINT fk_users_default_address \
+                    FOREIGN KEY (address_id) REFERENCES bazaar.address (address_id) \
+                    DEFERRABLE INITIALLY DEFERRED";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_deferrable_ignored_in_mysql() {
+    let db = Mysql::new();
+    let foreign_key = ForeignKey::new("fk_users_default_address",
+                                      vec!["address_id"],
+                                      "bazaar",
+                                      "address",
+                                      vec!["address_id"])
+                          .deferrable();
+    let users = Table { name: "users".to_string(), ..order_line() };
+
+    let frag = db.sql_builder().build_add_foreign_key(&users, &foreign_key);
+
+    let expected = "ALTER TABLE users ADD CONSTRAINT fk_users_default_address \
+                    FOREIGN KEY (address_id) REFERENCES address (address_id)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_set_constraints_deferred() {
+    let db = Postgres::new();
+    let frag = db.sql_builder().build_set_constraints_deferred().unwrap();
+    assert_eq!(frag.sql.trim(), "SET CONSTRAINTS ALL DEFERRED");
+}
+
+#[test]
+fn test_set_constraints_deferred_unsupported() {
+    let db = Mysql::new();
+    assert!(db.set_constraints_deferred().is_err());
+}
@@ -0,0 +1,104 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::Equality;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_group_by_rollup() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.columns(vec!["category_id", "brand"])
+         .from_table("bazaar.product")
+         .group_by_rollup(vec!["category_id", "brand"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, brand
+     FROM bazaar.product
+ GROUP BY ROLLUP (category_id, brand)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_group_by_cube() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.columns(vec!["category_id", "brand"])
+         .from_table("bazaar.product")
+         .group_by_cube(vec!["category_id", "brand"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, brand
+     FROM bazaar.product
+ GROUP BY CUBE (category_id, brand)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_mysql_group_by_rollup() {
+    let db = Mysql::new();
+    let mut query = Query::select();
+
+    query.columns(vec!["category_id", "brand"])
+         .from_table("product")
+         .group_by_rollup(vec!["category_id", "brand"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, brand
+     FROM product
+ GROUP BY category_id, brand WITH ROLLUP".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_mysql_group_by_cube() {
+    let db = Mysql::new();
+    let mut query = Query::select();
+
+    query.columns(vec!["category_id", "brand"])
+         .from_table("product")
+         .group_by_cube(vec!["category_id", "brand"]);
+
+    assert!(query.build(&db).is_err());
+}
+
+#[test]
+fn test_having_with_and() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.columns(vec!["category_id"])
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"])
+         .having_raw("count(*)", Equality::GT, &1)
+         .having_raw("sum(price)", Equality::GT, &0);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id
+     FROM bazaar.product
+ GROUP BY category_id 
+   HAVING count(*) > $1 
+      AND sum(price) > $2".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 2);
+}
@@ -0,0 +1,152 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::ColumnName;
+use rustorm::query::TableName;
+use rustorm::table::Table;
+use rustorm::platform::postgres::Postgres;
+
+
+#[test]
+fn test_plain_table_name() {
+    let table = TableName::from_str("bazaar.product");
+    assert_eq!(table.schema, Some("bazaar".to_string()));
+    assert_eq!(table.name, "product");
+}
+
+#[test]
+#[should_panic]
+fn test_table_name_with_semicolon() {
+    TableName::from_str("bazaar.product; DROP TABLE users");
+}
+
+#[test]
+#[should_panic]
+fn test_table_name_with_space() {
+    let mut query = Query::select_all();
+    query.from_table("product p");
+}
+
+#[test]
+fn test_empty_schema() {
+    let table = TableName::from_str(".product");
+    assert_eq!(table.schema, None);
+    assert_eq!(table.name, "product");
+}
+
+#[test]
+fn test_try_from_str() {
+    assert!(TableName::try_from_str("bazaar.product").is_ok());
+    assert!(TableName::try_from_str("bazaar.product; DROP TABLE users").is_err());
+    assert!(TableName::try_from_str("a.b.c").is_err());
+    assert!(TableName::try_from_str("bazaar.").is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_unbalanced_quote() {
+    TableName::from_str("bazaar.\"product");
+}
+
+#[test]
+#[should_panic]
+fn test_quote_breaking_out() {
+    TableName::from_str("\"product\"; DROP TABLE users; \"x\"");
+}
+
+#[test]
+fn test_quoted_table_name() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.\"order items; v2.1\"");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.\"order items; v2.1\"".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_table_without_schema() {
+    let table = Table {
+        schema: "".to_string(),
+        name: "product".to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![],
+        is_view: false,
+        foreign_keys: vec![],
+    };
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from(&table);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_column_try_from_str() {
+    assert!(ColumnName::try_from_str("product.name").is_ok());
+    assert!(ColumnName::try_from_str("product.*").is_ok());
+    assert!(ColumnName::try_from_str("name; DROP TABLE users").is_err());
+    assert!(ColumnName::try_from_str("a.b.c").is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_column_with_space() {
+    let mut query = Query::select();
+    query.column("name AS n");
+}
+
+#[test]
+#[should_panic]
+fn test_filter_column_with_semicolon() {
+    let mut query = Query::select_all();
+    query.filter_eq("name; DROP TABLE users", &"x");
+}
+
+#[test]
+#[should_panic]
+fn test_order_by_expression() {
+    let mut query = Query::select_all();
+    query.asc("name; DROP TABLE users");
+}
+
+#[test]
+#[should_panic]
+fn test_join_column_with_space() {
+    let mut query = Query::select_all();
+    query.from_table("product")
+         .left_join_table("category", "product.category_id = 1 OR 1", "category.category_id");
+}
+
+#[test]
+fn test_column_raw() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("name")
+         .column_raw("count(*)")
+         .from_table("product");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name, count(*)
+     FROM product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
@@ -0,0 +1,65 @@
+extern crate rustorm;
+
+use rustorm::database::Database;
+use rustorm::table::{Table, Index};
+use rustorm::query::{Operand, LOWER};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+fn table(name: &str) -> Table {
+    Table {
+        schema: "bazaar".to_string(),
+        name: name.to_string(),
+        parent_table: None,
+        sub_table: vec![],
+        comment: None,
+        columns: vec![],
+        is_view: false,
+        foreign_keys: vec![],
+    }
+}
+
+#[test]
+fn test_partial_index() {
+    let db = Postgres::new();
+    let index = Index::new("idx_product_active", vec!["active"]).predicate("active = true");
+    let frag = db.sql_builder().build_create_index(&table("product"), &index).unwrap();
+
+    let expected = "CREATE INDEX idx_product_active ON bazaar.product (active) WHERE active = true";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_unique_index_mysql() {
+    let db = Mysql::new();
+    let index = Index::new("idx_product_name", vec!["name", "seq_no"]).unique();
+    let frag = db.sql_builder().build_create_index(&table("product"), &index).unwrap();
+
+    let expected = "CREATE UNIQUE INDEX idx_product_name ON product (name, seq_no)";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_partial_index_mysql() {
+    let db = Mysql::new();
+    let index = Index::new("idx_product_active", vec!["active"]).predicate("active = true");
+    assert!(db.sql_builder().build_create_index(&table("product"), &index).is_err());
+}
+
+#[test]
+fn test_expression_index() {
+    let db = Postgres::new();
+    let index = Index::on_expressions("idx_users_email", vec![Operand::Function(LOWER("email"))])
+                    .unique();
+    let frag = db.sql_builder().build_create_index(&table("users"), &index).unwrap();
+
+    let expected = "CREATE UNIQUE INDEX idx_users_email ON bazaar.users (LOWER(email))";
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
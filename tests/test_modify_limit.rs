@@ -0,0 +1,68 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_mysql_delete_limit() {
+    let db = Mysql::new();
+    let mut query = Query::delete();
+    query.from_table("product")
+         .filter_lt("stocks", &1)
+         .limit(100);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+DELETE FROM product
+    WHERE stocks < ?
+    LIMIT 100".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_postgres_delete_limit() {
+    let db = Postgres::new();
+    let mut query = Query::delete();
+    query.from_table("bazaar.product")
+         .filter_lt("stocks", &1)
+         .limit(100);
+
+    assert!(query.build(&db).is_err());
+}
+
+#[test]
+fn test_mysql_delete_order_by_limit() {
+    let db = Mysql::new();
+    let mut query = Query::delete();
+    query.from_table("product")
+         .filter_lt("stocks", &1)
+         .asc("created")
+         .limit(100);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+DELETE FROM product
+    WHERE stocks < ?
+ ORDER BY created ASC
+    LIMIT 100".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_postgres_delete_order_by() {
+    let db = Postgres::new();
+    let mut query = Query::delete();
+    query.from_table("bazaar.product")
+         .filter_lt("stocks", &1)
+         .asc("created");
+
+    assert!(query.build(&db).is_err());
+}
@@ -0,0 +1,83 @@
+extern crate rustorm;
+
+use rustorm::query::{Query, Direction, SUM};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_order_by_collate() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.column("name")
+         .from_table("bazaar.product")
+         .asc("name")
+         .collate("name", "und-x-icu")
+         .desc("created");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = r#"
+   SELECT name
+     FROM bazaar.product
+ ORDER BY name COLLATE "und-x-icu" ASC, created DESC"#.to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_mysql_order_by_collate() {
+    let db = Mysql::new();
+    let mut query = Query::select();
+
+    query.column("name")
+         .from_table("bazaar.product")
+         .asc("name")
+         .collate("name", "utf8mb4_unicode_ci");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+ ORDER BY name COLLATE `utf8mb4_unicode_ci` ASC".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_order_by_position() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("category_id")
+         .field(SUM("price").AS("total"))
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"])
+         .order_by_position(2, Direction::DESC);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, SUM(price) AS total
+     FROM bazaar.product
+ GROUP BY category_id 
+ ORDER BY 2 DESC".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_order_by_position_out_of_fields() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("category_id")
+         .field(SUM("price").AS("total"))
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"])
+         .order_by_position(3, Direction::DESC);
+    assert!(query.build(&db).is_err());
+}
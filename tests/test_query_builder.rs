@@ -0,0 +1,506 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::{Field, Function, Operand, ColumnName, Equality};
+use rustorm::dao::Value;
+use rustorm::writer::KeywordCase;
+use rustorm::database::{Database, SqlOption};
+use rustorm::platform::postgres::Postgres;
+
+
+#[test]
+fn test_fork_query() {
+    let db = Postgres::new();
+    let mut base = Query::select_all();
+    base.from_table("bazaar.product")
+        .filter_eq("active", &true);
+
+    let mut fork = base.fork();
+    fork.filter_eq("name", &"GTX660 Ti videocard")
+        .limit(10);
+
+    let base_frag = base.build(&db).unwrap();
+    let fork_frag = fork.build(&db).unwrap();
+
+    let expected_base = "
+   SELECT *
+     FROM bazaar.product
+    WHERE active = $1".to_string();
+    let expected_fork = "
+   SELECT *
+     FROM bazaar.product
+    WHERE active = $1 
+      AND name = $2 
+    LIMIT 10".to_string();
+    println!("base: {{\n{}}}", base_frag.sql);
+    println!("fork: {{\n{}}}", fork_frag.sql);
+    assert!(base_frag.sql.trim() == expected_base.trim());
+    assert!(fork_frag.sql.trim() == expected_fork.trim());
+    assert_eq!(base_frag.params.len(), 1);
+    assert_eq!(fork_frag.params.len(), 2);
+}
+
+#[test]
+fn test_into_parts() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .filter_eq("name", &"GTX660 Ti videocard")
+         .filter_gt("stocks", &1);
+
+    let (sql, params) = query.build(&db).unwrap().into_parts();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE name = $1 
+      AND stocks > $2".to_string();
+    assert!(sql.trim() == expected.trim());
+    assert_eq!(params,
+               vec![Value::String("GTX660 Ti videocard".to_string()), Value::I32(1)]);
+}
+
+#[test]
+fn test_raw_filter() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("name")
+         .field(Field {
+             operand: Operand::Raw("ST_AsText(geom)".to_string()),
+             name: Some("location".to_string()),
+         })
+         .from_table("bazaar.store")
+         .filter_eq("active", &true)
+         .filter_raw("ST_DWithin(geom, ST_GeomFromText($1), $2)",
+                     vec![&"POINT(1 1)", &100])
+         .filter_eq("name", &"Shop");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name, ST_AsText(geom) AS location
+     FROM bazaar.store
+    WHERE active = $1 
+      AND ST_DWithin(geom, ST_GeomFromText($2 ), $3 )
+      AND name = $4".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params,
+               vec![Value::Bool(true),
+                    Value::String("POINT(1 1)".to_string()),
+                    Value::I32(100),
+                    Value::String("Shop".to_string())]);
+}
+
+#[test]
+fn test_lowercase_keywords() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.keyword_case(KeywordCase::Lower)
+         .from_table("bazaar.product")
+         .filter_eq("name", &"GTX660 Ti videocard")
+         .asc("name")
+         .limit(10);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   select *
+     from bazaar.product
+    where name = $1 
+ order by name asc
+    limit 10".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_reuse_params() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.reuse_params()
+         .from_table("bazaar.product")
+         .filter_gt("created", &"2015-10-01")
+         .filter_gt("updated", &"2015-10-01");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE created > $1 
+      AND updated > $1".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params, vec![Value::String("2015-10-01".to_string())]);
+}
+
+#[test]
+fn test_soft_delete_excludes_inactive() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .filter_eq("name", &"GTX660 Ti videocard")
+         .soft_delete("active");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE name = $1 
+      AND active = $2".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params[1], Value::Bool(true));
+}
+
+#[test]
+fn test_soft_delete_with_inactive() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .soft_delete("active")
+         .with_inactive();
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_soft_delete_with_join() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .left_join_table("bazaar.product_category",
+                          "product_category.product_id",
+                          "product.product_id")
+         .soft_delete("active");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+          LEFT JOIN bazaar.product_category 
+          ON product_category.product_id = product.product_id 
+    WHERE product.active = $1".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_build_without_connection() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .filter_eq("name", &"GTX660 Ti videocard");
+
+    let frag = query.build_for(vec![SqlOption::UsesNumberedParam, SqlOption::UsesSchema]).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE name = $1".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 1);
+}
+
+#[test]
+fn test_select_without_from() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.field(Function::new("uuid_generate_v4", vec![]).AS("id"));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT uuid_generate_v4() AS id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_select_without_from_and_fields() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    assert!(query.build(&db).is_err());
+}
+
+#[test]
+fn test_tag_comment() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .filter_eq("name", &"GTX660 Ti videocard")
+         .filter_eq("active", &true)
+         .tag("tenant:42");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "/* tenant:42 */
+   SELECT *
+     FROM bazaar.product
+    WHERE name = $1 
+      AND active = $2".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 2);
+}
+
+#[test]
+fn test_create_table_as() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.columns(vec!["product_id", "name"])
+         .from_table("bazaar.product")
+         .filter_eq("active", &true)
+         .filter_gt("price", &100);
+
+    let frag = db.sql_builder().build_create_table_as("bazaar.staging", &query).unwrap();
+
+    let expected = "CREATE TABLE bazaar.staging AS
+   SELECT product_id, name
+     FROM bazaar.product
+    WHERE active = $1 
+      AND price > $2".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params, vec![Value::Bool(true), Value::I32(100)]);
+}
+
+#[test]
+fn test_update_returning_expression() {
+    let db = Postgres::new();
+    let mut query = Query::update();
+    query.from_table("bazaar.category")
+         .set("name", &"Accessories")
+         .filter_eq("category_id", &7)
+         .returns(vec!["name"])
+         .return_field(Function::new("length",
+                                     vec![Operand::ColumnName(ColumnName::from_str("name"))])
+                           .AS("name_length"));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   UPDATE bazaar.category
+      SET name = $1 
+    WHERE category_id = $2 
+RETURNING name, length(name) AS name_length".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_select_only() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.only()
+         .from_table("bazaar.product")
+         .filter_eq("active", &true);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM ONLY bazaar.product
+    WHERE active = $1".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_select_only_ignored_without_inheritance() {
+    let mut query = Query::select_all();
+    query.only()
+         .from_table("product")
+         .filter_eq("active", &true);
+
+    let frag = query.build_for(vec![SqlOption::UsesQuestionMark]).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM product
+    WHERE active = ?".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_filter_on_range() {
+    let db = Postgres::new();
+    let mut sugar = Query::select_all();
+    sugar.from_table("bazaar.product");
+    sugar.filter_on("price").gte(&10).lte(&100);
+
+    let mut explicit = Query::select_all();
+    explicit.from_table("bazaar.product")
+            .filter_gte("price", &10)
+            .filter_lte("price", &100);
+
+    let frag = sugar.build(&db).unwrap();
+    let explicit_frag = explicit.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+    WHERE price >= $1 
+      AND price <= $2".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.sql, explicit_frag.sql);
+    assert_eq!(frag.params, explicit_frag.params);
+}
+
+#[test]
+fn test_in_list_question_marks() {
+    let mut query = Query::select_all();
+    let values = vec![Operand::Value(Value::I32(3)),
+                      Operand::Value(Value::I32(1)),
+                      Operand::Value(Value::I32(4)),
+                      Operand::Value(Value::I32(1))];
+    query.from_table("product")
+         .filter_eq("active", &true)
+         .filter_subquery("category_id", Equality::IN, Operand::Vec(values));
+
+    let frag = query.build_for(vec![SqlOption::UsesQuestionMark]).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM product
+    WHERE active = ?
+      AND category_id IN (?, ?, ?, ?)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params,
+               vec![Value::Bool(true), Value::I32(3), Value::I32(1), Value::I32(4), Value::I32(1)]);
+}
+
+#[test]
+fn test_schema_qualified_function() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    let compute = Function::new("compute",
+                                vec![Operand::ColumnName(ColumnName::from_str("price"))])
+                      .schema("analytics");
+    query.column("name")
+         .field(compute.AS("score"))
+         .from_table("bazaar.product");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name, analytics.compute(price) AS score
+     FROM bazaar.product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_insert_value_pairs() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.product")
+         .insert_value("price", &100)
+         .insert_value("description", &"to be excluded")
+         .insert_value("name", &"product1")
+         .insert_value("price", &200)
+         .exclude_column("description");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.product( price, name ) 
+   VALUES ($1 , $2 )".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params,
+               vec![Value::I32(200), Value::String("product1".to_string())]);
+}
+
+#[test]
+fn test_insert_returning_only_the_key() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .set("name", &"Electronics")
+         .returns(vec!["category_id"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.category( name ) 
+   VALUES ($1 ) 
+RETURNING category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_insert_multiple_rows() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .column("name")
+         .column("active")
+         .add_value_row(vec![Operand::Value(Value::String("Books".to_string())),
+                             Operand::Value(Value::Bool(true))])
+         .add_value_row(vec![Operand::Value(Value::String("Music".to_string())),
+                             Operand::Default]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.category( name, active ) 
+   VALUES ($1 , $2 ), ($3 , DEFAULT)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 3);
+}
+
+#[test]
+fn test_mixed_case_columns_are_quoted() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("OrderDate")
+         .column("name")
+         .from_table("bazaar.orders")
+         .filter_eq("CustomerID", &"c1")
+         .asc("OrderDate");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT \"OrderDate\", name
+     FROM bazaar.orders
+    WHERE \"CustomerID\" = $1 
+ ORDER BY \"OrderDate\" ASC".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
@@ -0,0 +1,36 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_replace_into_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .set("category_id", &1)
+         .set("name", &"Electronics")
+         .replace_into();
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+  REPLACE INTO category( category_id, name ) 
+   VALUES (?, ?)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 2);
+}
+
+#[test]
+fn test_replace_into_postgres() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .set("name", &"Electronics")
+         .replace_into();
+    assert!(query.build(&db).is_err());
+}
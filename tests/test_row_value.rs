@@ -0,0 +1,28 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::dao::ToValue;
+use rustorm::platform::postgres::Postgres;
+
+
+#[test]
+fn test_row_value_in() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    let first: Vec<&ToValue> = vec![&1, &2];
+    let second: Vec<&ToValue> = vec![&3, &4];
+
+    query.from_table("bazaar.order_line")
+         .filter_row_in(vec!["order_id", "product_id"], vec![first, second]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.order_line
+    WHERE (order_id, product_id) IN (($1 , $2 ), ($3 , $4 ))".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 4);
+}
@@ -0,0 +1,85 @@
+extern crate rustorm;
+
+use rustorm::query::{Query, Join, JoinType, SafetyLimits, ToTableName};
+
+
+fn cross_join(table: &str) -> Join {
+    Join {
+        modifier: None,
+        join_type: Some(JoinType::CROSS),
+        table_name: table.to_table_name(),
+        column1: vec![],
+        column2: vec![],
+        subquery: None,
+        lateral: false,
+    }
+}
+
+#[test]
+fn test_cartesian_join_rejected() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .join(cross_join("bazaar.category"))
+         .limit(10);
+
+    let result = query.validate_safety(&SafetyLimits::new());
+    println!("{:?}", result);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_join_with_condition_accepted() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .left_join_table("bazaar.product_category",
+                          "product_category.product_id",
+                          "product.product_id")
+         .filter_eq("product.name", &"GTX660 Ti videocard")
+         .limit(10);
+
+    let limits = SafetyLimits {
+        max_joins: Some(2),
+        require_filter: true,
+        require_limit_on_join: true,
+    };
+    assert_eq!(query.validate_safety(&limits), Ok(()));
+}
+
+#[test]
+fn test_too_many_joins() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .left_join_table("bazaar.product_category",
+                          "product_category.product_id",
+                          "product.product_id")
+         .left_join_table("bazaar.category",
+                          "category.category_id",
+                          "product_category.category_id");
+
+    let mut limits = SafetyLimits::new();
+    limits.max_joins = Some(1);
+    assert!(query.validate_safety(&limits).is_err());
+}
+
+#[test]
+fn test_missing_filter() {
+    let mut query = Query::delete();
+    query.from_table("bazaar.product");
+
+    let mut limits = SafetyLimits::new();
+    limits.require_filter = true;
+    assert!(query.validate_safety(&limits).is_err());
+}
+
+#[test]
+fn test_joined_select_without_limit() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .left_join_table("bazaar.product_category",
+                          "product_category.product_id",
+                          "product.product_id");
+
+    let mut limits = SafetyLimits::new();
+    limits.require_limit_on_join = true;
+    assert!(query.validate_safety(&limits).is_err());
+}
@@ -0,0 +1,75 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::STRING_AGG;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+#[cfg(feature = "sqlite")]
+use rustorm::platform::sqlite::Sqlite;
+
+
+#[test]
+fn test_string_agg_postgres() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.column("category_id")
+         .field(STRING_AGG("name", ", ").AS("names"))
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, STRING_AGG(name, $1 ) AS names
+     FROM bazaar.product
+ GROUP BY category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 1);
+}
+
+#[test]
+fn test_group_concat_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::select();
+
+    query.column("category_id")
+         .field(STRING_AGG("name", ", ").AS("names"))
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, GROUP_CONCAT(name SEPARATOR ', ') AS names
+     FROM product
+ GROUP BY category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert!(frag.params.is_empty());
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_group_concat_sqlite() {
+    let db = Sqlite::new();
+    let mut query = Query::select();
+
+    query.column("category_id")
+         .field(STRING_AGG("name", ", ").AS("names"))
+         .from_table("bazaar.product")
+         .group_by(vec!["category_id"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT category_id, GROUP_CONCAT(name, $1 ) AS names
+     FROM product
+ GROUP BY category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
@@ -0,0 +1,284 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::{Filter, Equality, Operand, ColumnName};
+use rustorm::query::{ANY, ALL};
+use rustorm::query::{Join, JoinType, ToTableName};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+use rustorm::dao::Value;
+use rustorm::database::{Database, SqlOption};
+
+
+#[test]
+fn test_scalar_subquery_field() {
+    let db = Postgres::new();
+
+    let mut order_count = Query::select();
+    order_count.column_raw("count(*)")
+               .from_table("orders")
+               .add_filter(Filter::bare_new(Operand::ColumnName(ColumnName::from_str("orders.user_id")),
+                                            Equality::EQ,
+                                            Operand::ColumnName(ColumnName::from_str("users.user_id"))));
+
+    let mut query = Query::select();
+    query.column("name")
+         .subquery(order_count, "order_count")
+         .from_table("users")
+         .filter_eq("active", &true);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name, (
+   SELECT count(*)
+     FROM orders
+    WHERE orders.user_id = users.user_id) AS order_count
+     FROM users
+    WHERE active = $1".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_greater_than_all() {
+    let db = Postgres::new();
+
+    let mut discounted = Query::select();
+    discounted.column("price")
+              .from_table("discounted")
+              .filter_eq("active", &true);
+
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_subquery("price", Equality::GT, ALL(discounted));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+    WHERE price > ALL (
+   SELECT price
+     FROM discounted
+    WHERE active = $1 )".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_equal_any() {
+    let db = Postgres::new();
+
+    let mut ordered = Query::select();
+    ordered.column("product_id")
+           .from_table("order_line");
+
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_subquery("product_id", Equality::EQ, ANY(ordered));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+    WHERE product_id = ANY (
+   SELECT product_id
+     FROM order_line)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_not_in_subquery() {
+    let db = Postgres::new();
+
+    // the NULLs are filtered out, otherwise NOT IN never matches
+    let mut ordered = Query::select();
+    ordered.column("product_id")
+           .from_table("order_line")
+           .add_filter(Filter::is_not_null("product_id"));
+
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_not_in_query("product_id", ordered);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+    WHERE product_id NOT IN (
+   SELECT product_id
+     FROM order_line
+    WHERE product_id IS NOT NULL)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_values_table() {
+    let db = Postgres::new();
+    let rows = vec![vec![Value::I32(1), Value::String("a".to_string())],
+                    vec![Value::I32(2), Value::String("b".to_string())]];
+    let mut query = Query::values_table(&rows);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   VALUES ($1 , $2 ), ($3 , $4 )".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 4);
+}
+
+#[test]
+fn test_select_from_values_table() {
+    let db = Postgres::new();
+    let rows = vec![vec![Value::I32(1), Value::String("a".to_string())],
+                    vec![Value::I32(2), Value::String("b".to_string())]];
+    let mut query = Query::select_all();
+    query.from_query(Query::values_table(&rows), "v")
+         .filter("column1", Equality::GT, &1);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM (
+   VALUES ($1 , $2 ), ($3 , $4 )) AS v
+    WHERE column1 > $5 ".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params.len(), 5);
+}
+
+#[test]
+fn test_in_subquery_param_numbering() {
+    let db = Postgres::new();
+
+    let mut ordered = Query::select();
+    ordered.column("product_id")
+           .from_table("order_line")
+           .filter_gt("quantity", &5)
+           .filter_lt("price", &100);
+
+    let mut query = Query::select();
+    query.column("name")
+         .from_table("product")
+         .filter_eq("active", &true)
+         .filter_in_query("product_id", ordered)
+         .filter_gt("price", &10);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name
+     FROM product
+    WHERE active = $1 
+      AND product_id IN (
+   SELECT product_id
+     FROM order_line
+    WHERE quantity > $2 
+      AND price < $3 )
+      AND price > $4 ".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+    assert_eq!(frag.params,
+               vec![Value::Bool(true), Value::I32(5), Value::I32(100), Value::I32(10)]);
+    for n in 1..5 {
+        assert_eq!(frag.sql.matches(&format!("${} ", n)).count(), 1);
+    }
+}
+
+fn latest_order() -> Query {
+    let mut latest = Query::select_all();
+    latest.from_table("bazaar.orders")
+          .add_filter(Filter::bare_new(Operand::ColumnName(ColumnName::from_str("orders.user_id")),
+                                       Equality::EQ,
+                                       Operand::ColumnName(ColumnName::from_str("users.user_id"))))
+          .desc("created")
+          .set_page_size(1);
+    latest
+}
+
+#[test]
+fn test_left_join_lateral() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("users.name")
+         .column("latest.created")
+         .from_table("bazaar.users")
+         .left_join_lateral(latest_order(), "latest");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT users.name, latest.created
+     FROM bazaar.users
+          LEFT JOIN LATERAL (
+   SELECT *
+     FROM bazaar.orders
+    WHERE orders.user_id = users.user_id
+ ORDER BY created DESC
+    LIMIT 1) AS latest 
+          ON TRUE".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_cross_join_without_on() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+    query.column("product.name")
+         .column("category.name")
+         .from_table("bazaar.product")
+         .join(Join {
+             modifier: None,
+             join_type: Some(JoinType::CROSS),
+             table_name: "bazaar.category".to_table_name(),
+             column1: vec![],
+             column2: vec![],
+             subquery: None,
+             lateral: false,
+         });
+
+    let frag = query.build(&db).unwrap();
+
+    println!("{}", frag.sql);
+    // only a lateral join is joined ON TRUE
+    assert!(frag.sql.contains("CROSS JOIN bazaar.category"));
+    assert!(!frag.sql.contains("ON TRUE"));
+}
+
+#[test]
+fn test_left_join_lateral_unsupported() {
+    let mut query = Query::select();
+    query.column("users.name")
+         .from_table("users")
+         .left_join_lateral(latest_order(), "latest");
+    assert!(query.build_for(vec![SqlOption::UsesNumberedParam]).is_err());
+}
+
+#[test]
+fn test_mysql_lateral_by_version() {
+    let db = Mysql::new();
+    assert!(!db.sql_options().contains(&SqlOption::SupportsLateral));
+    db.set_server_version("8.0.13");
+    assert!(!db.sql_options().contains(&SqlOption::SupportsLateral));
+    db.set_server_version("8.0.14");
+    assert!(db.sql_options().contains(&SqlOption::SupportsLateral));
+}
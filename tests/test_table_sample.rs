@@ -0,0 +1,59 @@
+extern crate rustorm;
+
+use rustorm::query::{Query, SampleMethod};
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_bernoulli_sample() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .sample(SampleMethod::BERNOULLI, 1.0);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product TABLESAMPLE BERNOULLI (1)".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_system_sample_with_filter() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .sample(SampleMethod::SYSTEM, 0.5)
+         .filter_eq("is_active", &true);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product TABLESAMPLE SYSTEM (0.5)
+    WHERE is_active = $1".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_sample_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::select_all();
+    query.from_table("product")
+         .sample(SampleMethod::BERNOULLI, 1.0);
+    assert!(query.build(&db).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_sample_over_100_percent() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .sample(SampleMethod::BERNOULLI, 150.0);
+}
@@ -0,0 +1,99 @@
+#![allow(non_upper_case_globals)]
+extern crate rustorm;
+extern crate uuid;
+
+use rustorm::query::Query;
+use rustorm::platform::postgres::Postgres;
+
+/// the typed columns, as they would be declared next to the table structs
+mod product {
+    use std::marker::PhantomData;
+    use uuid::Uuid;
+    use rustorm::query::TypedColumn;
+
+    pub const product_id: TypedColumn<Uuid> = TypedColumn {
+        name: "product.product_id",
+        column_type: PhantomData,
+    };
+}
+
+mod product_category {
+    use std::marker::PhantomData;
+    use uuid::Uuid;
+    use rustorm::query::TypedColumn;
+
+    pub const product_id: TypedColumn<Uuid> = TypedColumn {
+        name: "product_category.product_id",
+        column_type: PhantomData,
+    };
+    pub const category_id: TypedColumn<Uuid> = TypedColumn {
+        name: "product_category.category_id",
+        column_type: PhantomData,
+    };
+}
+
+mod category {
+    use std::marker::PhantomData;
+    use uuid::Uuid;
+    use rustorm::query::TypedColumn;
+
+    pub const category_id: TypedColumn<Uuid> = TypedColumn {
+        name: "category.category_id",
+        column_type: PhantomData,
+    };
+}
+
+
+#[test]
+fn test_typed_join() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .left_join_on(&"bazaar.product_category",
+                       product::product_id.eq(product_category::product_id))
+         .left_join_on(&"bazaar.category",
+                       category::category_id.eq(product_category::category_id));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+          LEFT JOIN bazaar.product_category 
+          ON product.product_id = product_category.product_id 
+          LEFT JOIN bazaar.category 
+          ON category.category_id = product_category.category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_typed_join_more_conditions() {
+    let db = Postgres::new();
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .left_join_on(&"bazaar.product_category",
+                       product::product_id.eq(product_category::product_id))
+         .ON(product::product_id.eq(product_category::category_id));
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT *
+     FROM bazaar.product
+          LEFT JOIN bazaar.product_category 
+          ON product.product_id = product_category.product_id 
+          AND product.product_id = product_category.category_id".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+#[should_panic]
+fn test_on_without_join() {
+    let mut query = Query::select_all();
+    query.from_table("bazaar.product")
+         .ON(product::product_id.eq(product_category::product_id));
+}
@@ -0,0 +1,73 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::database::SqlOption;
+use rustorm::platform::postgres::Postgres;
+use rustorm::platform::mysql::Mysql;
+
+
+#[test]
+fn test_on_conflict_update_returning_inserted() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .set("category_id", &1)
+         .set("name", &"Electronics")
+         .on_conflict_update(vec!["category_id"], vec!["name"])
+         .return_all()
+         .return_inserted();
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.category( category_id, name ) 
+   VALUES ($1 , $2 ) 
+       ON CONFLICT (category_id) 
+       DO UPDATE SET name = EXCLUDED.name 
+RETURNING *, (xmax = 0) AS inserted".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_on_conflict_do_nothing() {
+    let db = Postgres::new();
+    let mut query = Query::insert();
+    query.into_table("bazaar.category")
+         .set("name", &"Electronics")
+         .on_conflict_do_nothing(vec!["name"]);
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   INSERT INTO bazaar.category( name ) 
+   VALUES ($1 ) 
+       ON CONFLICT (name) 
+       DO NOTHING".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_on_conflict_mysql() {
+    let db = Mysql::new();
+    let mut query = Query::insert();
+    query.into_table("category")
+         .set("name", &"Electronics")
+         .on_conflict_do_nothing(vec!["name"]);
+    assert!(query.build(&db).is_err());
+}
+
+#[test]
+fn test_return_inserted_without_xmax() {
+    let mut query = Query::insert();
+    query.into_table("category")
+         .set("name", &"Electronics")
+         .on_conflict_update(vec!["category_id"], vec!["name"])
+         .return_inserted();
+    assert!(query.build_for(vec![SqlOption::UsesNumberedParam,
+                                 SqlOption::SupportsReturningClause,
+                                 SqlOption::SupportsOnConflict]).is_err());
+}
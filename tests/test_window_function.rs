@@ -0,0 +1,97 @@
+extern crate rustorm;
+
+use rustorm::query::Query;
+use rustorm::query::{Window, ROW_NUMBER, RANK, SUM};
+use rustorm::query::{FrameBound, Function, Operand, ColumnName};
+use rustorm::platform::postgres::Postgres;
+
+
+#[test]
+fn test_row_number() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.column("name")
+         .field(ROW_NUMBER()
+                    .OVER(Window::new()
+                              .partition_by(vec!["category_id"])
+                              .desc("price"))
+                    .AS("rn"))
+         .from_table("bazaar.product");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name, ROW_NUMBER() OVER (PARTITION BY category_id ORDER BY price DESC) AS rn
+     FROM bazaar.product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_rank() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.column("name")
+         .field(RANK().OVER(Window::new().asc("name")).AS("name_rank"))
+         .from_table("bazaar.product");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT name, RANK() OVER (ORDER BY name ASC) AS name_rank
+     FROM bazaar.product".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_running_total() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.column("order_id")
+         .field(SUM("amount")
+                    .OVER(Window::new()
+                              .partition_by(vec!["customer_id"])
+                              .asc("created")
+                              .rows_between(FrameBound::UNBOUNDED_PRECEDING,
+                                            FrameBound::CURRENT_ROW))
+                    .AS("running_total"))
+         .from_table("bazaar.orders");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT order_id, SUM(amount) OVER (PARTITION BY customer_id ORDER BY created ASC ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW) AS running_total
+     FROM bazaar.orders".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}
+
+#[test]
+fn test_moving_average() {
+    let db = Postgres::new();
+    let mut query = Query::select();
+
+    query.column("created")
+         .field(Function::new("AVG", vec![Operand::ColumnName(ColumnName::from_str("amount"))])
+                    .OVER(Window::new()
+                              .asc("created")
+                              .rows_between(FrameBound::PRECEDING(2), FrameBound::CURRENT_ROW))
+                    .AS("moving_avg"))
+         .from_table("bazaar.orders");
+
+    let frag = query.build(&db).unwrap();
+
+    let expected = "
+   SELECT created, AVG(amount) OVER (ORDER BY created ASC ROWS BETWEEN 2 PRECEDING AND CURRENT ROW) AS moving_avg
+     FROM bazaar.orders".to_string();
+    println!("actual:   {{\n{}}} [{}]", frag.sql, frag.sql.len());
+    println!("expected: {{{}}} [{}]", expected, expected.len());
+    assert!(frag.sql.trim() == expected.trim());
+}